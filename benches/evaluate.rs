@@ -0,0 +1,44 @@
+// Compares CH evaluation using the angle addition recurrence against computing a sin/cos per band.
+// Run with `cargo bench --bench evaluate`
+
+use circular_harmonics::circ_harmonics::{CircularHarmonics, Normalization};
+use std::f32::consts::PI;
+use std::hint::black_box;
+use std::time::Instant;
+
+fn evaluate_per_band(ch: &CircularHarmonics, angle: f32) -> f32 {
+    let mut accum = ch.band0() / (2.0 * PI).sqrt();
+    for band in 1..ch.band_count() {
+        let (s, c) = (angle * band as f32).sin_cos();
+        let (x, y) = ch.band(band);
+        accum += (x * c + y * s) / PI.sqrt();
+    }
+
+    accum
+}
+
+fn time_per_eval(iterations: usize, f: impl Fn(f32) -> f32) -> f64 {
+    let start = Instant::now();
+    let mut accum = 0.0;
+    for i in 0..iterations {
+        accum += f(black_box(i as f32 * 0.001));
+    }
+    black_box(accum);
+    start.elapsed().as_nanos() as f64 / iterations as f64
+}
+
+fn main() {
+    let iterations = 1_000_000;
+    for bands in [6, 20, 64] {
+        let ch = CircularHarmonics::from_pulse(bands, 1.0, 1.0, Normalization::Raw);
+        let per_band = time_per_eval(iterations, |angle| evaluate_per_band(&ch, angle));
+        let recurrence = time_per_eval(iterations, |angle| ch.evaluate(angle));
+        println!(
+            "{:2} bands: per band sin/cos {:7.2} ns, recurrence {:7.2} ns ({:.1}x)",
+            bands,
+            per_band,
+            recurrence,
+            per_band / recurrence
+        );
+    }
+}
@@ -1,73 +1,708 @@
 // https://blackpawn.com/texts/ch/default.html
 
-use std::f32::consts::PI;
-use std::ops::{Add, Sub};
+use crate::complex::Complex;
+use crate::fft;
+use crate::lobes;
+use crate::polynomial;
+use crate::scalar::Scalar;
+use std::fmt;
+use std::ops::{Add, Mul, Sub};
 
-fn term_to_band(term: usize) -> usize {
+pub(crate) fn term_to_band(term: usize) -> usize {
     (term + 1) / 2
 }
 
-fn calculate_term(term: usize, angle: f32) -> f32 {
-    let band = term_to_band(term);
+pub(crate) fn calculate_term<T: Scalar>(term: usize, angle: T) -> T {
+    let band = T::from_usize(term_to_band(term));
     match term {
-        0 => 1.0 / (2.0 * PI).sqrt(),
-        term if (term % 2) != 0 => (angle * band as f32).cos() / PI.sqrt(),
-        _ => (angle * band as f32).sin() / PI.sqrt(),
+        0 => T::ONE / (T::from_f64(2.0) * T::PI).sqrt(),
+        term if (term % 2) != 0 => (angle * band).cos() / T::PI.sqrt(),
+        _ => (angle * band).sin() / T::PI.sqrt(),
     }
 }
 
 // Returns the indefinite integral for a box, for each term
-fn term_integral_box(term: usize, angle: f32) -> f32 {
-    let band = term_to_band(term);
+fn term_integral_box<T: Scalar>(term: usize, angle: T) -> T {
+    let band = T::from_usize(term_to_band(term));
     match term {
-        0 => angle / (2.0 * PI).sqrt(),
+        0 => angle / (T::from_f64(2.0) * T::PI).sqrt(),
         term if (term % 2) != 0 => {
             // https://www.wolframalpha.com/input/?i=integral++cos%284theta%29%2Fsqrt%28pi%29
-            (angle * band as f32).sin() / (band as f32 * PI.sqrt())
+            (angle * band).sin() / (band * T::PI.sqrt())
         }
         _ => {
             // https://www.wolframalpha.com/input/?i=integral++sin%284theta%29%2Fsqrt%28pi%29
-            -(angle * band as f32).cos() / (band as f32 * PI.sqrt())
+            -(angle * band).cos() / (band * T::PI.sqrt())
         }
     }
 }
 
-pub struct CircularHarmonics {
+// Antiderivative of angle·term, used to integrate linear functions against each term
+fn term_integral_ramp<T: Scalar>(term: usize, angle: T) -> T {
+    let band = T::from_usize(term_to_band(term));
+    let (s, c) = (angle * band).sin_cos();
+    match term {
+        0 => angle * angle * T::from_f64(0.5) / (T::from_f64(2.0) * T::PI).sqrt(),
+        // ∫ θ·cos(kθ) = θ·sin(kθ) / k + cos(kθ) / k²
+        term if (term % 2) != 0 => (angle * s / band + c / (band * band)) / T::PI.sqrt(),
+        // ∫ θ·sin(kθ) = -θ·cos(kθ) / k + sin(kθ) / k²
+        _ => (-angle * c / band + s / (band * band)) / T::PI.sqrt(),
+    }
+}
+
+// Returns the Gauss-Legendre nodes and weights for the [-1, 1] interval
+fn gauss_legendre<T: Scalar>(n: usize) -> Vec<(T, T)> {
+    // Newton iteration on the Legendre polynomial P_n, done in f64 so larger n stay accurate
+    (0..n)
+        .map(|i| {
+            let mut x = (std::f64::consts::PI * (i as f64 + 0.75) / (n as f64 + 0.5)).cos();
+            let mut dp = 1.0;
+            for _ in 0..100 {
+                // Evaluate P_n(x) and P_{n-1}(x) with the three-term recurrence
+                let (mut p0, mut p1) = (1.0, x);
+                for k in 2..=n {
+                    let p2 = ((2 * k - 1) as f64 * x * p1 - (k - 1) as f64 * p0) / k as f64;
+                    p0 = p1;
+                    p1 = p2;
+                }
+                let (p, p_prev) = if n == 0 { (1.0, 0.0) } else { (p1, p0) };
+                dp = n as f64 * (x * p - p_prev) / (x * x - 1.0);
+                let dx = p / dp;
+                x -= dx;
+                if dx.abs() < 1e-15 {
+                    break;
+                }
+            }
+            (T::from_f64(x), T::from_f64(2.0 / ((1.0 - x * x) * dp * dp)))
+        })
+        .collect()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Quadrature {
+    // Midpoint rule on uniformly spaced samples. Spectrally accurate for smooth periodic functions
+    Uniform,
+    // Trapezoid rule, including both 0 and 2π as samples
+    Trapezoid,
+    // Gauss-Legendre nodes over [0, 2π]. Doesn't assume periodicity
+    GaussLegendre,
+}
+
+// Solves A·x = b in place for a symmetric positive definite A (stored row-major, n x n),
+// using a Cholesky decomposition. Returns None if A isn't positive definite.
+fn solve_cholesky<T: Scalar>(a: &mut [T], b: &mut [T], n: usize) -> Option<()> {
+    for j in 0..n {
+        let mut d = a[j * n + j];
+        for k in 0..j {
+            d -= a[j * n + k] * a[j * n + k];
+        }
+        if d <= T::ZERO || !d.is_finite() {
+            return None;
+        }
+        let d = d.sqrt();
+        a[j * n + j] = d;
+        for i in (j + 1)..n {
+            let mut v = a[i * n + j];
+            for k in 0..j {
+                v -= a[i * n + k] * a[j * n + k];
+            }
+            a[i * n + j] = v / d;
+        }
+    }
+
+    // Forward substitution with L, then back substitution with L^T
+    for i in 0..n {
+        for k in 0..i {
+            let v = a[i * n + k] * b[k];
+            b[i] -= v;
+        }
+        b[i] /= a[i * n + i];
+    }
+    for i in (0..n).rev() {
+        for k in (i + 1)..n {
+            let v = a[k * n + i] * b[k];
+            b[i] -= v;
+        }
+        b[i] /= a[i * n + i];
+    }
+
+    Some(())
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Regularization<T: Scalar = f32> {
+    None,
+    // Penalizes the energy of the coefficients: λ·Σc²
+    Tikhonov(T),
+    // Penalizes the energy of the second derivative (∫f''² = Σk⁴·c²), which favors smooth fits
+    // and leaves band 0 untouched
+    Laplacian(T),
+}
+
+pub struct LeastSquaresFit<T: Scalar = f32> {
+    pub ch: CircularHarmonics<T>,
+    // Residuals are value - fitted value, for each sample
+    pub residuals: Vec<T>,
+    pub rms_error: T,
+    pub max_abs_error: T,
+    // Σ weight·residual²
+    pub weighted_error: T,
+}
+
+// Error of a band-limited approximation of a function, measured over the whole circle
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ApproximationError<T: Scalar = f32> {
+    // sqrt(∫(f - ch)²)
+    pub l2: T,
+    // max |f - ch|, estimated on a dense set of samples
+    pub linf: T,
+}
+
+// Windows used to suppress ringing. All of them are 1 at band 0 and fall off towards the
+// highest band, so high frequencies get attenuated.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WindowKind<T: Scalar = f32> {
+    // Lanczos sigma factors: sinc(k / N)
+    Lanczos,
+    Hann,
+    Hamming,
+    // Gaussian, with its standard deviation given as a fraction of the band count
+    Gaussian(T),
+    // Fejér / Cesàro summation: 1 - k / N
+    Fejer,
+}
+
+// Returns the factor the given band is scaled by when applying a window
+pub(crate) fn window_weight<T: Scalar>(window: WindowKind<T>, band: usize, band_count: usize) -> T {
+    let x = T::from_usize(band) / T::from_usize(band_count);
+    let half = T::from_f64(0.5);
+    match window {
+        WindowKind::Lanczos if band == 0 => T::ONE,
+        WindowKind::Lanczos => (T::PI * x).sin() / (T::PI * x),
+        WindowKind::Hann => half + half * (T::PI * x).cos(),
+        WindowKind::Hamming => T::from_f64(0.54) + T::from_f64(0.46) * (T::PI * x).cos(),
+        WindowKind::Gaussian(_) if band == 0 => T::ONE,
+        WindowKind::Gaussian(sigma) => (-half * (x / sigma).powi(2)).exp(),
+        WindowKind::Fejer => T::ONE - x,
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChError {
+    // A CH needs at least band 0
+    NoBands,
+    // Coefficients come in pairs after band 0, so there must be an odd number of them
+    EvenCoeffCount(usize),
+    BandOutOfRange { band: usize, band_count: usize },
+    // Band 0 only has one coefficient, so it's accessed with band0() / set_band0()
+    Band0NotAccessible,
+    BandCountMismatch { expected: usize, actual: usize },
+    // The least-squares normal equations couldn't be solved
+    SingularSystem,
+    // Polyline points have to be sorted by angle, and span less than 2π
+    UnsortedPolyline,
+    // Accessing a CH past the end of a batch
+    IndexOutOfRange { index: usize, len: usize },
+    // Inputs that go together have different lengths
+    LengthMismatch { expected: usize, actual: usize },
+}
+
+impl fmt::Display for ChError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ChError::NoBands => write!(f, "A CH needs at least 1 band"),
+            ChError::EvenCoeffCount(count) => write!(
+                f,
+                "A CH needs an odd number of coefficients, but got {}",
+                count
+            ),
+            ChError::BandOutOfRange { band, band_count } => write!(
+                f,
+                "Accessing band {}, but this CH only has {} bands",
+                band, band_count
+            ),
+            ChError::Band0NotAccessible => {
+                write!(f, "Band 0 cannot be accessed through this interface")
+            }
+            ChError::BandCountMismatch { expected, actual } => {
+                write!(f, "Expected a CH with {} bands, got {}", expected, actual)
+            }
+            ChError::SingularSystem => write!(
+                f,
+                "Not enough samples to fit this many bands without regularization"
+            ),
+            ChError::UnsortedPolyline => write!(
+                f,
+                "Polyline points must be sorted by angle, and span less than 2π"
+            ),
+            ChError::IndexOutOfRange { index, len } => {
+                write!(f, "Accessing CH {}, but this batch only has {}", index, len)
+            }
+            ChError::LengthMismatch { expected, actual } => {
+                write!(f, "Expected {} values, got {}", expected, actual)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ChError {}
+
+// Every band count has to include band 0
+pub(crate) fn check_band_count(band_count: usize) -> Result<(), ChError> {
+    if band_count == 0 {
+        return Err(ChError::NoBands);
+    }
+    Ok(())
+}
+
+// Amplitude/phase form of a CH. Band k contributes amplitude·cos(k·θ - phase), so the band's
+// (x, y) is amplitude·(cos(phase), sin(phase)), and rotating by α adds k·α to the phase.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PolarBands<T: Scalar = f32> {
+    pub band0: T,
+    // (amplitude, phase) for bands 1 and up
+    pub bands: Vec<(T, T)>,
+}
+
+// What the strength passed to a constructor means
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Normalization {
+    // Coefficients are multiplied by strength. Each constructor documents what that means
+    Raw,
+    // ∫f = strength
+    UnitIntegral,
+    // max |f| = |strength|, and a negative strength flips the function upside down. This is the
+    // peak of the band-limited function, ringing included
+    UnitPeak,
+    // sqrt(∫f²) = strength
+    UnitEnergy,
+}
+
+// Shape of the lobes fit_lobes decomposes a CH into
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LobeKind<T: Scalar = f32> {
+    // from_von_mises lobes with this κ. Amplitudes are the peak values of the lobes
+    VonMises { kappa: T },
+    // from_impulse lobes. Amplitudes are the areas under the impulses
+    Impulse,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Lobe<T: Scalar = f32> {
+    pub direction: T,
+    pub amplitude: T,
+}
+
+// Antiderivative of a CH: a linear ramp (from band 0) plus a periodic part, which is itself a CH
+pub struct Cumulative<T: Scalar = f32> {
+    slope: T,
+    periodic: CircularHarmonics<T>,
+    offset: T,
+}
+
+impl<T: Scalar> Cumulative<T> {
+    // ∫[0, angle] f. Keeps growing past 2π, so F(θ + 2π) = F(θ) + total()
+    pub fn evaluate(&self, angle: T) -> T {
+        self.slope * angle + self.periodic.evaluate(angle) - self.offset
+    }
+
+    // Integral over the full circle
+    pub fn total(&self) -> T {
+        self.slope * T::from_f64(2.0) * T::PI
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CircularHarmonics<T: Scalar = f32> {
     // Band:         0 |   1    |   2    |   3    | ...
     // Coefficients: 0 | [1, 2] | [3, 4] | [5, 6] | ...
-    coeffs: Vec<f32>,
+    coeffs: Vec<T>,
 }
 
-impl CircularHarmonics {
-    pub fn new(band_count: usize) -> CircularHarmonics {
+impl<T: Scalar> CircularHarmonics<T> {
+    pub fn new(band_count: usize) -> CircularHarmonics<T> {
+        Self::try_new(band_count).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_new(band_count: usize) -> Result<CircularHarmonics<T>, ChError> {
+        check_band_count(band_count)?;
+        Ok(CircularHarmonics {
+            coeffs: vec![T::ZERO; band_count * 2 - 1],
+        })
+    }
+
+    pub fn from_coeffs(coeffs: Vec<T>) -> CircularHarmonics<T> {
+        Self::try_from_coeffs(coeffs).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_from_coeffs(coeffs: Vec<T>) -> Result<CircularHarmonics<T>, ChError> {
+        if coeffs.is_empty() {
+            return Err(ChError::NoBands);
+        }
+        if coeffs.len() % 2 != 1 {
+            return Err(ChError::EvenCoeffCount(coeffs.len()));
+        }
+
+        Ok(CircularHarmonics { coeffs })
+    }
+
+    // A Dirac delta at the given angle. With Normalization::Raw, strength is the area under the
+    // delta, which makes it the same as Normalization::UnitIntegral.
+    pub fn from_impulse(
+        band_count: usize,
+        angle: T,
+        strength: T,
+        normalization: Normalization,
+    ) -> CircularHarmonics<T> {
+        let mut result = Self::new(band_count);
+        for (i, c) in result.coeffs.iter_mut().enumerate() {
+            *c = calculate_term(i, angle);
+        }
+        result.normalized(normalization, strength)
+    }
+
+    // A box centered at angle 0. With Normalization::Raw, strength is the height of the box.
+    pub fn from_pulse(
+        band_count: usize,
+        pulse_width: T,
+        strength: T,
+        normalization: Normalization,
+    ) -> CircularHarmonics<T> {
+        let half_width = pulse_width * T::from_f64(0.5);
+        let mut result = Self::new(band_count);
+        for (i, c) in result.coeffs.iter_mut().enumerate() {
+            *c = term_integral_box(i, half_width) - term_integral_box(i, -half_width);
+        }
+        result.normalized(normalization, strength)
+    }
+
+    // Scales the CH so the quantity picked by the normalization equals strength. Raw just
+    // multiplies the coefficients by strength. If the quantity is 0 there's nothing to scale,
+    // and the CH is returned unchanged.
+    pub fn normalized(&self, normalization: Normalization, strength: T) -> CircularHarmonics<T> {
+        let current = match normalization {
+            Normalization::Raw => T::ONE,
+            Normalization::UnitIntegral => self.coeffs[0] * (T::from_f64(2.0) * T::PI).sqrt(),
+            Normalization::UnitPeak => self.max_value().max(-self.min_value()),
+            Normalization::UnitEnergy => self.norm(),
+        };
+        let scale = if current == T::ZERO {
+            T::ONE
+        } else {
+            strength / current
+        };
+
         CircularHarmonics {
-            coeffs: vec![0.0; band_count * 2 - 1],
+            coeffs: self.coeffs.iter().map(|&c| c * scale).collect(),
+        }
+    }
+
+    // Box of the given strength over the arc going counterclockwise from start to end, so an
+    // end smaller than start wraps around through 2π. When end and start are 2π or more apart,
+    // in either order, the arc covers the whole circle.
+    pub fn from_arc(band_count: usize, start: T, end: T, strength: T) -> CircularHarmonics<T> {
+        let two_pi = T::from_f64(2.0) * T::PI;
+        let width = if (end - start).abs() >= two_pi {
+            two_pi
+        } else if end < start {
+            end - start + two_pi
+        } else {
+            end - start
+        };
+
+        let mut result = Self::new(band_count);
+        for (i, c) in result.coeffs.iter_mut().enumerate() {
+            *c = strength * (term_integral_box(i, start + width) - term_integral_box(i, start));
         }
+
+        result
+    }
+
+    // Step function made of (start, end, value) arcs, like the ones from_arc takes. Values add
+    // up where arcs overlap, and are 0 where there is no arc.
+    pub fn from_piecewise_constant(band_count: usize, arcs: &[(T, T, T)]) -> CircularHarmonics<T> {
+        let mut result = Self::new(band_count);
+        for &(start, end, value) in arcs {
+            let arc = Self::from_arc(band_count, start, end, value);
+            for (c, a) in result.coeffs.iter_mut().zip(arc.coeffs.iter()) {
+                *c += *a;
+            }
+        }
+
+        result
     }
 
-    pub fn from_coeffs(coeffs: Vec<f32>) -> CircularHarmonics {
-        CircularHarmonics { coeffs }
+    // Exact projection of the periodic polyline through the given (angle, value) points, which
+    // must be sorted by angle and span less than a full turn. The last point connects back to
+    // the first one, 2π later.
+    pub fn from_piecewise_linear(band_count: usize, points: &[(T, T)]) -> CircularHarmonics<T> {
+        Self::try_from_piecewise_linear(band_count, points).unwrap_or_else(|e| panic!("{}", e))
     }
 
-    pub fn from_impulse(band_count: usize, angle: f32, strength: f32) -> CircularHarmonics {
-        let coeff_count = band_count * 2 - 1;
-        let mut coeffs = vec![0.0; coeff_count];
-        for i in 0..coeff_count {
-            coeffs[i] = strength * calculate_term(i, angle); // TODO -- Is multiplying by strength OK here?
+    pub fn try_from_piecewise_linear(
+        band_count: usize,
+        points: &[(T, T)],
+    ) -> Result<CircularHarmonics<T>, ChError> {
+        let two_pi = T::from_f64(2.0) * T::PI;
+        let mut result = Self::try_new(band_count)?;
+        let (first, last) = match (points.first(), points.last()) {
+            (Some(&first), Some(&last)) => (first, last),
+            _ => return Ok(result),
+        };
+        if !(points.windows(2).all(|w| w[0].0 <= w[1].0) && last.0 - first.0 < two_pi) {
+            return Err(ChError::UnsortedPolyline);
         }
-        CircularHarmonics { coeffs }
+
+        let closing = (first.0 + two_pi, first.1);
+        for (&(a, va), &(b, vb)) in points
+            .iter()
+            .zip(points.iter().skip(1).chain(std::iter::once(&closing)))
+        {
+            if b <= a {
+                // Vertical step, which has no area
+                continue;
+            }
+
+            // f(θ) = va + slope·(θ - a) over [a, b]
+            let slope = (vb - va) / (b - a);
+            for (i, c) in result.coeffs.iter_mut().enumerate() {
+                let box_integral = term_integral_box(i, b) - term_integral_box(i, a);
+                let ramp_integral = term_integral_ramp(i, b) - term_integral_ramp(i, a);
+                *c += va * box_integral + slope * (ramp_integral - a * box_integral);
+            }
+        }
+
+        Ok(result)
     }
 
-    pub fn from_pulse(band_count: usize, pulse_width: f32, strength: f32) -> CircularHarmonics {
-        let coeff_count = band_count * 2 - 1;
-        let mut coeffs = vec![0.0; coeff_count];
-        for i in 0..coeff_count {
-            coeffs[i] = strength
-                * (term_integral_box(i, pulse_width * 0.5)
-                    - term_integral_box(i, -pulse_width * 0.5));
-            // TODO -- Is multiplying by strength OK here?
+    // Builds an even lobe centered at the given angle, from the integrals of the lobe against
+    // cos(kθ) (see lobes.rs)
+    fn from_lobe(
+        band_count: usize,
+        center: T,
+        integral: impl Fn(usize) -> f64,
+    ) -> CircularHarmonics<T> {
+        let mut result = Self::new(band_count);
+        result.coeffs[0] = T::from_f64(integral(0) / (2.0 * std::f64::consts::PI).sqrt());
+        for band in 1..band_count {
+            let x = T::from_f64(integral(band) / std::f64::consts::PI.sqrt());
+            result.set_band(band, x, T::ZERO);
         }
-        CircularHarmonics { coeffs }
+
+        result.rotate(center)
+    }
+
+    // max(cos(θ - center), 0)
+    pub fn from_clamped_cosine(band_count: usize, center: T) -> CircularHarmonics<T> {
+        Self::from_lobe(band_count, center, lobes::clamped_cosine)
+    }
+
+    // e^(κ·(cos(θ - center) - 1)), the circular analog of a Gaussian, with a peak of 1.
+    // Higher κ makes the lobe narrower, roughly like a Gaussian with σ = 1 / √κ.
+    pub fn from_von_mises(band_count: usize, center: T, kappa: T) -> CircularHarmonics<T> {
+        assert!(kappa >= T::ZERO, "von Mises κ can't be negative");
+        let integrals = lobes::von_mises(kappa.to_f64(), band_count);
+        Self::from_lobe(band_count, center, |band| integrals[band])
+    }
+
+    // Triangle that peaks at 1 at the center, and reaches 0 half_width away from it
+    pub fn from_tent(band_count: usize, center: T, half_width: T) -> CircularHarmonics<T> {
+        assert!(
+            half_width > T::ZERO && half_width <= T::PI,
+            "Lobe half width must be in (0, π]"
+        );
+        let half_width = half_width.to_f64();
+        Self::from_lobe(band_count, center, |band| lobes::tent(half_width, band))
+    }
+
+    // Hann-shaped lobe: ½·(1 + cos(π·(θ - center) / half_width)) within half_width of the center
+    pub fn from_raised_cosine(band_count: usize, center: T, half_width: T) -> CircularHarmonics<T> {
+        assert!(
+            half_width > T::ZERO && half_width <= T::PI,
+            "Lobe half width must be in (0, π]"
+        );
+        let half_width = half_width.to_f64();
+        Self::from_lobe(band_count, center, |band| {
+            lobes::raised_cosine(half_width, band)
+        })
+    }
+
+    // max(cos(θ - center), 0)ⁿ, like a Phong specular lobe
+    pub fn from_phong(band_count: usize, center: T, exponent: T) -> CircularHarmonics<T> {
+        assert!(exponent >= T::ZERO, "Phong exponent can't be negative");
+        let exponent = exponent.to_f64();
+        Self::from_lobe(band_count, center, |band| lobes::phong(exponent, band))
+    }
+
+    // Projects an arbitrary function of the angle into CH by numerically integrating it against
+    // each term over [0, 2π], using the given number of samples.
+    pub fn project(
+        band_count: usize,
+        f: impl Fn(T) -> T,
+        samples: usize,
+        quadrature: Quadrature,
+    ) -> CircularHarmonics<T> {
+        let two_pi = T::from_f64(2.0) * T::PI;
+        let nodes: Vec<(T, T)> = match quadrature {
+            Quadrature::Uniform => {
+                let step = two_pi / T::from_usize(samples);
+                (0..samples)
+                    .map(|i| ((T::from_usize(i) + T::from_f64(0.5)) * step, step))
+                    .collect()
+            }
+            Quadrature::Trapezoid => {
+                assert!(
+                    samples >= 2,
+                    "Trapezoid quadrature needs at least 2 samples"
+                );
+                let step = two_pi / T::from_usize(samples - 1);
+                (0..samples)
+                    .map(|i| {
+                        let w = if i == 0 || i == samples - 1 {
+                            step * T::from_f64(0.5)
+                        } else {
+                            step
+                        };
+                        (T::from_usize(i) * step, w)
+                    })
+                    .collect()
+            }
+            Quadrature::GaussLegendre => gauss_legendre(samples)
+                .into_iter()
+                .map(|(x, w): (T, T)| ((x + T::ONE) * T::PI, w * T::PI))
+                .collect(),
+        };
+
+        let mut result = Self::new(band_count);
+        for (angle, weight) in nodes {
+            let v = f(angle) * weight;
+            for (i, c) in result.coeffs.iter_mut().enumerate() {
+                *c += v * calculate_term(i, angle);
+            }
+        }
+
+        result
+    }
+
+    // Projects a ring of uniformly spaced samples, where samples[j] is the value at angle
+    // 2π·j/n, using a real FFT. Bands above the Nyquist limit (n / 2) are left at 0.
+    pub fn from_samples(samples: &[T], band_count: usize) -> CircularHarmonics<T> {
+        let n = samples.len();
+        assert!(n > 0, "Can't project an empty ring of samples");
+        let spectrum = fft::rfft(samples);
+
+        let mut result = Self::new(band_count);
+        let two_pi = T::from_f64(2.0) * T::PI;
+        let scale = two_pi / T::from_usize(n);
+        result.coeffs[0] = spectrum[0].re * scale / two_pi.sqrt();
+        for (band, x) in spectrum.iter().enumerate().take(band_count).skip(1) {
+            // The Nyquist band is shared by both halves of the spectrum, so it only gets half
+            let nyquist_scale = if band * 2 == n {
+                T::from_f64(0.5)
+            } else {
+                T::ONE
+            };
+            let s = scale * nyquist_scale / T::PI.sqrt();
+            result.set_band(band, x.re * s, -x.im * s);
+        }
+
+        result
+    }
+
+    // Evaluates the CH at n uniformly spaced angles (2π·j/n) with an inverse FFT.
+    // Bands above n / 2 alias, exactly as they would if evaluated one by one.
+    pub fn to_samples(&self, n: usize) -> Vec<T> {
+        if n == 0 {
+            return vec![];
+        }
+
+        let mut spectrum = vec![Complex::default(); n];
+        spectrum[0].re = T::from_usize(n) * self.coeffs[0] / (T::from_f64(2.0) * T::PI).sqrt();
+        for band in 1..self.band_count() {
+            let (x, y) = self.band(band);
+            let c = Complex::new(x, -y).scale(T::from_usize(n) * T::from_f64(0.5) / T::PI.sqrt());
+            let pos = band % n;
+            let neg = (n - pos) % n;
+            spectrum[pos] = spectrum[pos] + c;
+            spectrum[neg] = spectrum[neg] + c.conj();
+        }
+
+        fft::ifft(&mut spectrum);
+        spectrum.iter().map(|x| x.re).collect()
+    }
+
+    // Weighted least-squares fit to samples at arbitrary angles, solving the normal equations
+    // (AᵀWA + λR)·c = AᵀW·v. Fails with ChError::SingularSystem when there aren't enough
+    // (non-zero weight) samples for the requested bands and no regularization, and with
+    // ChError::LengthMismatch when values or weights don't have one entry per angle.
+    pub fn fit_least_squares(
+        angles: &[T],
+        values: &[T],
+        weights: Option<&[T]>,
+        band_count: usize,
+        regularization: Regularization<T>,
+    ) -> Result<LeastSquaresFit<T>, ChError> {
+        check_band_count(band_count)?;
+        for len in std::iter::once(values.len()).chain(weights.map(|w| w.len())) {
+            if len != angles.len() {
+                return Err(ChError::LengthMismatch {
+                    expected: angles.len(),
+                    actual: len,
+                });
+            }
+        }
+
+        let n = band_count * 2 - 1;
+        let mut ata = vec![T::ZERO; n * n];
+        let mut atv = vec![T::ZERO; n];
+        let mut terms = vec![T::ZERO; n];
+        for (i, (&angle, &value)) in angles.iter().zip(values.iter()).enumerate() {
+            let w = weights.map_or(T::ONE, |w| w[i]);
+            for (t, term) in terms.iter_mut().enumerate() {
+                *term = calculate_term(t, angle);
+            }
+            for r in 0..n {
+                atv[r] += w * terms[r] * value;
+                for c in 0..n {
+                    ata[r * n + c] += w * terms[r] * terms[c];
+                }
+            }
+        }
+
+        for i in 0..n {
+            ata[i * n + i] += match regularization {
+                Regularization::None => T::ZERO,
+                Regularization::Tikhonov(lambda) => lambda,
+                Regularization::Laplacian(lambda) => {
+                    lambda * T::from_usize(term_to_band(i)).powi(4)
+                }
+            };
+        }
+
+        solve_cholesky(&mut ata, &mut atv, n).ok_or(ChError::SingularSystem)?;
+        let ch = CircularHarmonics::from_coeffs(atv);
+
+        let residuals: Vec<T> = angles
+            .iter()
+            .zip(values.iter())
+            .map(|(&angle, &value)| value - ch.evaluate(angle))
+            .collect();
+        let sample_count = T::from_usize(residuals.len().max(1));
+        let rms_error = (residuals.iter().map(|&r| r * r).sum::<T>() / sample_count).sqrt();
+        let max_abs_error = residuals.iter().fold(T::ZERO, |m, r| m.max(r.abs()));
+        let weighted_error = residuals
+            .iter()
+            .enumerate()
+            .map(|(i, &r)| weights.map_or(T::ONE, |w| w[i]) * r * r)
+            .sum();
+
+        Ok(LeastSquaresFit {
+            ch,
+            residuals,
+            rms_error,
+            max_abs_error,
+            weighted_error,
+        })
     }
 
     pub fn coeff_count(&self) -> usize {
@@ -78,20 +713,38 @@ impl CircularHarmonics {
         (self.coeffs.len() + 1) / 2
     }
 
-    pub fn evaluate(&self, angle: f32) -> f32 {
-        let mut accum = 0.0;
-        for (i, v) in self.coeffs.iter().enumerate() {
-            accum += v * calculate_term(i, angle);
+    // Only one sin_cos is needed no matter the band count: (cos(kθ), sin(kθ)) for each band
+    // comes from rotating the previous band's by θ, using the angle addition formulas.
+    pub fn evaluate(&self, angle: T) -> T {
+        let (s, c) = angle.sin_cos();
+        let (mut band_s, mut band_c) = (T::ZERO, T::ONE);
+        let mut bands = T::ZERO;
+        for band in 1..self.band_count() {
+            let next_c = band_c * c - band_s * s;
+            let next_s = band_s * c + band_c * s;
+            band_c = next_c;
+            band_s = next_s;
+            bands += self.coeffs[band * 2 - 1] * band_c + self.coeffs[band * 2] * band_s;
         }
 
-        accum
+        self.coeffs[0] / (T::from_f64(2.0) * T::PI).sqrt() + bands / T::PI.sqrt()
+    }
+
+    pub fn evaluate_many(&self, angles: &[T]) -> Vec<T> {
+        angles.iter().map(|&angle| self.evaluate(angle)).collect()
     }
 
-    pub fn rotate(&self, angle: f32) -> CircularHarmonics {
+    // Evaluates the CH at n uniformly spaced angles (2π·j/n). Same as to_samples, which is
+    // already the fastest way to do it.
+    pub fn evaluate_uniform(&self, n: usize) -> Vec<T> {
+        self.to_samples(n)
+    }
+
+    pub fn rotate(&self, angle: T) -> CircularHarmonics<T> {
         let mut result = Self::new(self.band_count());
         result.coeffs[0] = self.coeffs[0];
         for band in 1..self.band_count() {
-            let (s, c) = (angle * band as f32).sin_cos();
+            let (s, c) = (angle * T::from_usize(band)).sin_cos();
             let (bandx, bandy) = self.band(band);
             let rotx = bandx * c - bandy * s;
             let roty = bandy * c + bandx * s;
@@ -101,71 +754,722 @@ impl CircularHarmonics {
         result
     }
 
-    pub fn band(&self, n: usize) -> (f32, f32) {
-        if n >= self.band_count() {
-            panic!("Accessing bands this CH does not have");
+    // Truncates the CH to band_count bands, or pads it with zero bands
+    pub fn with_band_count(&self, band_count: usize) -> CircularHarmonics<T> {
+        let mut result = Self::new(band_count);
+        for (dst, src) in result.coeffs.iter_mut().zip(self.coeffs.iter()) {
+            *dst = *src;
         }
 
-        if n == 0 {
-            panic!("Band 0 cannot be accessed through this interface");
+        result
+    }
+
+    // Returns g(θ) = f(m·θ), which repeats f m times around the circle. cos(k·mθ) and sin(k·mθ)
+    // are just the basis functions of band k·m, so the coefficients are moved there unchanged.
+    pub fn resample_scaled(&self, m: usize) -> CircularHarmonics<T> {
+        if m == 0 {
+            // f(0) everywhere
+            let mut result = Self::new(1);
+            result.coeffs[0] = self.evaluate(T::ZERO) * (T::from_f64(2.0) * T::PI).sqrt();
+            return result;
+        }
+
+        let mut result = Self::new((self.band_count() - 1) * m + 1);
+        result.coeffs[0] = self.coeffs[0];
+        for band in 1..self.band_count() {
+            let (x, y) = self.band(band);
+            result.set_band(band * m, x, y);
         }
-        (self.coeffs[(n * 2) - 1], self.coeffs[n * 2])
+
+        result
     }
 
-    pub fn band0(&self) -> f32 {
-        self.coeffs[0]
+    pub fn to_polar(&self) -> PolarBands<T> {
+        PolarBands {
+            band0: self.coeffs[0],
+            bands: (1..self.band_count())
+                .map(|band| {
+                    let (x, y) = self.band(band);
+                    ((x * x + y * y).sqrt(), y.atan2(x))
+                })
+                .collect(),
+        }
     }
 
-    pub fn set_band(&mut self, n: usize, a: f32, b: f32) {
+    pub fn from_polar(polar: &PolarBands<T>) -> CircularHarmonics<T> {
+        let mut result = Self::new(polar.bands.len() + 1);
+        result.coeffs[0] = polar.band0;
+        for (i, &(amplitude, phase)) in polar.bands.iter().enumerate() {
+            let (s, c) = phase.sin_cos();
+            result.set_band(i + 1, amplitude * c, amplitude * s);
+        }
+
+        result
+    }
+
+    // Energy in each band: band0² for band 0, and x² + y² for the rest. They add up to ∫f².
+    pub fn power_spectrum(&self) -> Vec<T> {
+        let mut result = vec![self.coeffs[0] * self.coeffs[0]];
+        result.extend((1..self.band_count()).map(|band| {
+            let (x, y) = self.band(band);
+            x * x + y * y
+        }));
+
+        result
+    }
+
+    // Σ weight·ch, zero-extending the CH with fewer bands
+    fn weighted_sum(terms: &[(&CircularHarmonics<T>, T)]) -> CircularHarmonics<T> {
+        let band_count = terms
+            .iter()
+            .map(|(ch, _)| ch.band_count())
+            .max()
+            .unwrap_or(1);
+        let mut result = Self::new(band_count);
+        for &(ch, weight) in terms {
+            for (c, &v) in result.coeffs.iter_mut().zip(ch.coeffs.iter()) {
+                *c += v * weight;
+            }
+        }
+
+        result
+    }
+
+    // Linear blend, a·(1 - t) + b·t. Blending two lobes pointing in different directions gives
+    // two smaller lobes rather than one in between, use slerp_bands for that.
+    pub fn lerp(a: &CircularHarmonics<T>, b: &CircularHarmonics<T>, t: T) -> CircularHarmonics<T> {
+        Self::weighted_sum(&[(a, T::ONE - t), (b, t)])
+    }
+
+    // Blends each band in polar form: amplitudes are interpolated linearly, and phases along
+    // the shortest rotation. A lobe blended with a rotated copy of itself then moves across
+    // instead of fading out and back in. Band k turns k times faster than band 1, so each band's
+    // phase change is the one closest to k times band 1's, which keeps all bands rotating
+    // together even when the higher ones wrap around.
+    pub fn slerp_bands(
+        a: &CircularHarmonics<T>,
+        b: &CircularHarmonics<T>,
+        t: T,
+    ) -> CircularHarmonics<T> {
+        let band_count = a.band_count().max(b.band_count());
+        let a = a.with_band_count(band_count).to_polar();
+        let b = b.with_band_count(band_count).to_polar();
+        let two_pi = T::from_f64(2.0) * T::PI;
+        let wrap = |angle: T| (angle + T::PI).rem_euclid(two_pi) - T::PI;
+        let lerp = |x: T, y: T| x + (y - x) * t;
+
+        let phase_delta = |&(amplitude_a, phase_a): &(T, T), &(amplitude_b, phase_b): &(T, T)| {
+            // A band with no amplitude has no meaningful phase
+            if amplitude_a == T::ZERO || amplitude_b == T::ZERO {
+                None
+            } else {
+                Some(wrap(phase_b - phase_a))
+            }
+        };
+        let rotation = match (a.bands.first(), b.bands.first()) {
+            (Some(band_a), Some(band_b)) => phase_delta(band_a, band_b).unwrap_or(T::ZERO),
+            _ => T::ZERO,
+        };
+
+        let bands = a
+            .bands
+            .iter()
+            .zip(b.bands.iter())
+            .enumerate()
+            .map(|(i, (band_a, band_b))| {
+                let expected = rotation * T::from_usize(i + 1);
+                let phase = match phase_delta(band_a, band_b) {
+                    Some(delta) => band_a.1 + (expected + wrap(delta - expected)) * t,
+                    None if band_a.0 == T::ZERO => band_b.1,
+                    None => band_a.1,
+                };
+                (lerp(band_a.0, band_b.0), phase)
+            })
+            .collect();
+
+        Self::from_polar(&PolarBands {
+            band0: lerp(a.band0, b.band0),
+            bands,
+        })
+    }
+
+    // Bilinear interpolation between the 4 corners of a grid cell, with (u, v) in [0, 1]².
+    // c10 is at u = 1 and v = 0.
+    pub fn bilinear(
+        c00: &CircularHarmonics<T>,
+        c10: &CircularHarmonics<T>,
+        c01: &CircularHarmonics<T>,
+        c11: &CircularHarmonics<T>,
+        u: T,
+        v: T,
+    ) -> CircularHarmonics<T> {
+        let (iu, iv) = (T::ONE - u, T::ONE - v);
+        Self::weighted_sum(&[(c00, iu * iv), (c10, u * iv), (c01, iu * v), (c11, u * v)])
+    }
+
+    // Interpolation inside a triangle, with barycentric weights that should add up to 1
+    pub fn barycentric(
+        a: &CircularHarmonics<T>,
+        b: &CircularHarmonics<T>,
+        c: &CircularHarmonics<T>,
+        weights: (T, T, T),
+    ) -> CircularHarmonics<T> {
+        Self::weighted_sum(&[(a, weights.0), (b, weights.1), (c, weights.2)])
+    }
+
+    // Returns g(θ) = f(-θ). cos is even and sin is odd, so only the sin coefficients change.
+    pub fn negate_angle(&self) -> CircularHarmonics<T> {
+        let mut result = self.clone();
+        for band in 1..self.band_count() {
+            let (x, y) = self.band(band);
+            result.set_band(band, x, -y);
+        }
+
+        result
+    }
+
+    // Reflects the function about the axis at the given angle: g(θ) = f(2·axis_angle - θ),
+    // which is negating the angle and then rotating by 2·axis_angle
+    pub fn mirror(&self, axis_angle: T) -> CircularHarmonics<T> {
+        self.negate_angle().rotate(axis_angle * T::from_f64(2.0))
+    }
+
+    // Scales each band in place by the given window, to reduce ringing
+    pub fn apply_window(&mut self, window: WindowKind<T>) {
+        let band_count = self.band_count();
+        for (i, c) in self.coeffs.iter_mut().enumerate() {
+            *c *= window_weight(window, term_to_band(i), band_count);
+        }
+    }
+
+    // Returns the n-th derivative of the function, which is also a CH with the same band count
+    pub fn derivative(&self, order: usize) -> CircularHarmonics<T> {
+        let mut result = Self::new(self.band_count());
+        for band in 1..self.band_count() {
+            // d/dθ (x·cos(kθ) + y·sin(kθ)) = k·y·cos(kθ) - k·x·sin(kθ)
+            let k = T::from_usize(band);
+            let (mut x, mut y) = self.band(band);
+            for _ in 0..order {
+                let (dx, dy) = (k * y, -k * x);
+                x = dx;
+                y = dy;
+            }
+            result.set_band(band, x, y);
+        }
+        if order == 0 {
+            result.coeffs[0] = self.coeffs[0];
+        }
+
+        result
+    }
+
+    // Exact integral of the function over [a, b]. b can be smaller than a, and the interval can
+    // cover more than one turn.
+    pub fn integral_between(&self, a: T, b: T) -> T {
+        self.coeffs
+            .iter()
+            .enumerate()
+            .map(|(i, &c)| c * (term_integral_box(i, b) - term_integral_box(i, a)))
+            .sum()
+    }
+
+    // Returns F(θ) = ∫[0, θ] f, in closed form. If f is a non-negative distribution, this is
+    // its CDF (up to normalization).
+    pub fn cumulative(&self) -> Cumulative<T> {
+        let mut periodic = Self::new(self.band_count());
+        for band in 1..self.band_count() {
+            // ∫ x·cos(kθ) + y·sin(kθ) = (x·sin(kθ) - y·cos(kθ)) / k
+            let k = T::from_usize(band);
+            let (x, y) = self.band(band);
+            periodic.set_band(band, -y / k, x / k);
+        }
+        let offset = periodic.evaluate(T::ZERO);
+
+        Cumulative {
+            slope: self.coeffs[0] / (T::from_f64(2.0) * T::PI).sqrt(),
+            periodic,
+            offset,
+        }
+    }
+
+    // ∫ f·g over the circle. The basis is orthonormal, so that's just the dot product of the
+    // coefficients. Missing bands count as 0.
+    pub fn dot(&self, other: &CircularHarmonics<T>) -> T {
+        self.coeffs
+            .iter()
+            .zip(other.coeffs.iter())
+            .map(|(&a, &b)| a * b)
+            .sum()
+    }
+
+    // L2 norm, sqrt(∫f²)
+    pub fn norm(&self) -> T {
+        self.dot(self).sqrt()
+    }
+
+    // L2 distance between both functions
+    pub fn distance(&self, other: &CircularHarmonics<T>) -> T {
+        (self - other).norm()
+    }
+
+    // Distance to a reference function, relative to the reference's norm. Against a zero
+    // reference, this is 0 if both are equal, and infinite otherwise.
+    pub fn relative_error(&self, reference: &CircularHarmonics<T>) -> T {
+        let distance = self.distance(reference);
+        if distance == T::ZERO {
+            return T::ZERO;
+        }
+
+        distance / reference.norm()
+    }
+
+    // Projects f into band_count bands, and measures how far the result is from f. Useful to
+    // choose how many bands a function needs.
+    pub fn approximation_error(f: impl Fn(T) -> T, band_count: usize) -> ApproximationError<T> {
+        let samples = 512.max(band_count * 16);
+        let ch = Self::project(band_count, &f, samples, Quadrature::GaussLegendre);
+
+        let mut l2 = T::ZERO;
+        for (x, w) in gauss_legendre::<T>(samples) {
+            let angle = (x + T::ONE) * T::PI;
+            let diff = f(angle) - ch.evaluate(angle);
+            l2 += diff * diff * w * T::PI;
+        }
+
+        // The Gauss-Legendre nodes cluster at the ends and can miss the worst spot, so the peak
+        // error is measured on a dense uniform grid instead
+        let mut linf = T::ZERO;
+        for i in 0..samples * 4 {
+            let angle = T::from_usize(i) * T::from_f64(2.0) * T::PI / T::from_usize(samples * 4);
+            linf = linf.max((f(angle) - ch.evaluate(angle)).abs());
+        }
+
+        ApproximationError {
+            l2: l2.sqrt(),
+            linf,
+        }
+    }
+
+    // Returns all angles in [0, 2π) where the function crosses (or touches) zero, sorted.
+    // A CH with N bands is a trigonometric polynomial, f(θ) = Σ c[k]·e^(ikθ) for |k| < N, so with
+    // z = e^(iθ), z^(N-1)·f(θ) is a regular polynomial in z. Its roots come from the eigenvalues
+    // of its companion matrix, and the ones lying on the unit circle are the roots of f.
+    pub fn roots(&self) -> Vec<T> {
+        use std::f64::consts::PI;
+
+        let coeffs: Vec<f64> = self.coeffs.iter().map(|c| c.to_f64()).collect();
+        let evaluate = |angle: f64| -> (f64, f64) {
+            let (mut value, mut slope) = (coeffs[0] / (2.0 * PI).sqrt(), 0.0);
+            for band in 1..self.band_count() {
+                let k = band as f64;
+                let (s, c) = (angle * k).sin_cos();
+                let (x, y) = (coeffs[band * 2 - 1], coeffs[band * 2]);
+                value += (x * c + y * s) / PI.sqrt();
+                slope += k * (y * c - x * s) / PI.sqrt();
+            }
+            (value, slope)
+        };
+
+        // Polynomial coefficients, from z⁰ (band -(N-1)) up to z^(2N-2) (band N-1)
+        let n = self.band_count() - 1;
+        let mut poly = vec![Complex::default(); 2 * n + 1];
+        poly[n] = Complex::new(coeffs[0] / (2.0 * PI).sqrt(), 0.0);
+        for band in 1..=n {
+            let c = Complex::new(coeffs[band * 2 - 1], -coeffs[band * 2]).scale(0.5 / PI.sqrt());
+            poly[n + band] = c;
+            poly[n - band] = c.conj();
+        }
+
+        // If the highest bands are 0, the polynomial has extra roots at z = 0, which get
+        // discarded along with every other root that's off the unit circle
+        let scale = coeffs.iter().map(|c| c.abs()).sum::<f64>();
+        if scale == 0.0 {
+            return vec![];
+        }
+
+        let mut result: Vec<f64> = vec![];
+        for z in polynomial::roots(&poly) {
+            if (z.abs() - 1.0).abs() > 1e-3 {
+                continue;
+            }
+
+            // Polish with a few Newton iterations on the real function
+            let mut angle = z.arg();
+            for _ in 0..8 {
+                let (value, slope) = evaluate(angle);
+                if slope == 0.0 || value == 0.0 {
+                    break;
+                }
+                let step = value / slope;
+                if step.abs() > 1e-2 {
+                    break;
+                }
+                angle -= step;
+            }
+
+            if evaluate(angle).0.abs() > 1e-6 * scale {
+                continue;
+            }
+
+            let angle = angle.rem_euclid(2.0 * PI);
+            let is_duplicate = result.iter().any(|a| {
+                let d = (a - angle).abs();
+                d.min(2.0 * PI - d) < 1e-4
+            });
+            if !is_duplicate {
+                result.push(angle);
+            }
+        }
+
+        result.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        result.into_iter().map(T::from_f64).collect()
+    }
+
+    // Returns all angles in [0, 2π) where the function has a local minimum or maximum, sorted.
+    // These are the roots of the derivative, so they're found the same way as roots().
+    pub fn extrema(&self) -> Vec<T> {
+        self.derivative(1).roots()
+    }
+
+    // Returns the (angle, value) of the global minimum, checking every extremum.
+    fn find_min(&self) -> (T, T) {
+        self.extrema()
+            .into_iter()
+            .map(|angle| (angle, self.evaluate(angle)))
+            .fold((T::ZERO, self.evaluate(T::ZERO)), |a, b| {
+                if b.1 < a.1 {
+                    b
+                } else {
+                    a
+                }
+            })
+    }
+
+    fn find_max(&self) -> (T, T) {
+        self.extrema()
+            .into_iter()
+            .map(|angle| (angle, self.evaluate(angle)))
+            .fold((T::ZERO, self.evaluate(T::ZERO)), |a, b| {
+                if b.1 > a.1 {
+                    b
+                } else {
+                    a
+                }
+            })
+    }
+
+    // Global minimum value of the function
+    pub fn min_value(&self) -> T {
+        self.find_min().1
+    }
+
+    // Angle in [0, 2π) where the global minimum is
+    pub fn argmin(&self) -> T {
+        self.find_min().0
+    }
+
+    // Global maximum value of the function
+    pub fn max_value(&self) -> T {
+        self.find_max().1
+    }
+
+    // Angle in [0, 2π) where the global maximum is
+    pub fn argmax(&self) -> T {
+        self.find_max().0
+    }
+
+    // Modifies the CH so evaluate(θ) >= 0 everywhere, changing it as little as possible.
+    // First it looks for the mildest window between no windowing and a Fejér window that
+    // removes the negative lobes (Fejér sums of non-negative signals are non-negative).
+    // If the function has negative parts that windowing can't fix, band 0 gets raised instead,
+    // either on the original or on the windowed function, whichever ends up closer.
+    pub fn make_nonnegative(&mut self) {
+        if self.min_value() >= T::ZERO {
+            return;
+        }
+
+        let band_count = T::from_usize(self.band_count());
+        let windowed = |strength: T| {
+            let mut result = self.clone();
+            for (i, c) in result.coeffs.iter_mut().enumerate() {
+                *c *= T::ONE - strength * T::from_usize(term_to_band(i)) / band_count;
+            }
+            result
+        };
+
+        // Evaluation has rounding errors that grow with the band count, so leave some room
+        let margin = T::EPSILON * band_count * self.coeffs.iter().map(|c| c.abs()).sum::<T>();
+
+        let mut result = windowed(T::ONE);
+        if result.min_value() >= margin {
+            let (mut lo, mut hi) = (T::ZERO, T::ONE);
+            for _ in 0..20 {
+                let mid = (lo + hi) * T::from_f64(0.5);
+                if windowed(mid).min_value() >= margin {
+                    hi = mid;
+                } else {
+                    lo = mid;
+                }
+            }
+            result = windowed(hi);
+        } else {
+            // Raising the original keeps all of its detail, but windowing first can need a much
+            // smaller lift, so keep whichever ends up closer to the original
+            let lifted = |mut ch: CircularHarmonics<T>| {
+                let lift = (margin - ch.min_value()) * (T::from_f64(2.0) * T::PI).sqrt();
+                ch.coeffs[0] += lift;
+                ch
+            };
+            let lifted_original = lifted(windowed(T::ZERO));
+            let lifted_windowed = lifted(result);
+            result = if lifted_original.distance(self) <= lifted_windowed.distance(self) {
+                lifted_original
+            } else {
+                lifted_windowed
+            };
+        }
+
+        self.coeffs = result.coeffs;
+    }
+
+    // Direction the function points to the most. Band 1 alone gives a good estimate, like the
+    // linear band of SH does in 3D, but higher bands can move the actual peak, so the estimate
+    // is refined to the closest local maximum. Returns None for constant functions.
+    pub fn dominant_direction(&self) -> Option<T> {
+        if self.band_count() < 2 {
+            return None;
+        }
+
+        let two_pi = T::from_f64(2.0) * T::PI;
+        let second_derivative = self.derivative(2);
+        let maxima: Vec<T> = self
+            .extrema()
+            .into_iter()
+            .filter(|&angle| second_derivative.evaluate(angle) < T::ZERO)
+            .collect();
+
+        let (x, y) = self.band(1);
+        if x == T::ZERO && y == T::ZERO {
+            // Symmetric functions have no band 1, so just go for the highest peak
+            return if maxima.is_empty() {
+                None
+            } else {
+                Some(self.argmax())
+            };
+        }
+        let estimate = y.atan2(x).rem_euclid(two_pi);
+
+        let circular_distance = |angle: T| {
+            let d = (angle - estimate).rem_euclid(two_pi);
+            d.min(two_pi - d)
+        };
+        Some(
+            maxima
+                .into_iter()
+                .fold(None, |best: Option<T>, angle| match best {
+                    Some(b) if circular_distance(b) <= circular_distance(angle) => Some(b),
+                    _ => Some(angle),
+                })
+                .unwrap_or(estimate),
+        )
+    }
+
+    // Approximates the function as a sum of `count` lobes of the given kind. Lobes are picked
+    // greedily, each one at the direction that best correlates with what the previous ones left
+    // unexplained. Then all the amplitudes are solved together with least squares, since the
+    // lobes aren't orthogonal to each other. Fewer lobes are returned once what's left is just
+    // rounding noise compared to the first lobe.
+    pub fn fit_lobes(&self, count: usize, kind: LobeKind<T>) -> Vec<Lobe<T>> {
+        let band_count = self.band_count();
+        let lobe = |direction: T| match kind {
+            LobeKind::VonMises { kappa } => Self::from_von_mises(band_count, direction, kappa),
+            LobeKind::Impulse => {
+                Self::from_impulse(band_count, direction, T::ONE, Normalization::Raw)
+            }
+        };
+        let template = lobe(T::ZERO);
+        let template_energy = template.dot(&template);
+        if template_energy == T::ZERO {
+            return vec![];
+        }
+
+        let mut lobes: Vec<Lobe<T>> = vec![];
+        let mut residual = self.clone();
+        let mut tolerance = T::ZERO;
+        for _ in 0..count {
+            // The lobes are even, so correlating the residual with a lobe at every direction is
+            // the same as convolving it with the lobe
+            let correlation = residual.convolve(&template);
+            let (direction, value) = correlation.find_max();
+            if value <= tolerance {
+                break;
+            }
+            if lobes.is_empty() {
+                tolerance = value * T::EPSILON.sqrt();
+            }
+            let amplitude = value / template_energy;
+            residual = &residual - &lobe(direction).normalized(Normalization::Raw, amplitude);
+            lobes.push(Lobe {
+                direction,
+                amplitude,
+            });
+        }
+
+        // Solve the normal equations for all the amplitudes at once. If lobes ended up on top of
+        // each other the system is singular, and the greedy amplitudes are kept.
+        let shapes: Vec<CircularHarmonics<T>> = lobes.iter().map(|l| lobe(l.direction)).collect();
+        let n = shapes.len();
+        let mut gram = vec![T::ZERO; n * n];
+        let mut rhs: Vec<T> = shapes.iter().map(|shape| shape.dot(self)).collect();
+        for i in 0..n {
+            for j in 0..n {
+                gram[i * n + j] = shapes[i].dot(&shapes[j]);
+            }
+        }
+        if solve_cholesky(&mut gram, &mut rhs, n).is_some() {
+            for (lobe, amplitude) in lobes.iter_mut().zip(rhs) {
+                lobe.amplitude = amplitude;
+            }
+        }
+
+        lobes
+    }
+
+    pub fn band(&self, n: usize) -> (T, T) {
+        self.try_band(n).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_band(&self, n: usize) -> Result<(T, T), ChError> {
+        self.check_band(n)?;
+        Ok((self.coeffs[(n * 2) - 1], self.coeffs[n * 2]))
+    }
+
+    fn check_band(&self, n: usize) -> Result<(), ChError> {
         if n >= self.band_count() {
-            panic!("Accessing bands this CH does not have");
+            return Err(ChError::BandOutOfRange {
+                band: n,
+                band_count: self.band_count(),
+            });
         }
 
         if n == 0 {
-            panic!("Band 0 cannot be accessed through this interface");
+            return Err(ChError::Band0NotAccessible);
         }
+        Ok(())
+    }
+
+    pub fn band0(&self) -> T {
+        self.coeffs[0]
+    }
+
+    pub fn set_band(&mut self, n: usize, a: T, b: T) {
+        self.try_set_band(n, a, b)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_set_band(&mut self, n: usize, a: T, b: T) -> Result<(), ChError> {
+        self.check_band(n)?;
         self.coeffs[(n * 2) - 1] = a;
         self.coeffs[n * 2] = b;
+        Ok(())
     }
 
-    pub fn set_band0(&mut self, v: f32) {
+    pub fn set_band0(&mut self, v: T) {
         self.coeffs[0] = v;
     }
 
-    /*
-    pub fn convolve(&self, other: &CircularHarmonics) -> CircularHarmonics {
+    // Circular convolution (f * g)(θ) = ∫ f(φ) g(θ - φ) dφ. Each band behaves like a complex
+    // number (x - iy), so convolving boils down to a per-band complex multiplication.
+    // For a symmetric (zonal) kernel, the y components are 0 and this is just a per-band scale.
+    // The result keeps the band count of self; bands the kernel doesn't have are treated as 0.
+    pub fn convolve(&self, kernel: &CircularHarmonics<T>) -> CircularHarmonics<T> {
+        let mut result = Self::new(self.band_count());
+        let k0 = *kernel.coeffs.first().unwrap_or(&T::ZERO);
+        result.coeffs[0] = (T::from_f64(2.0) * T::PI).sqrt() * self.coeffs[0] * k0;
 
-        THIS IS WRONG
-        let result_term_count = self.coeff_count();
+        for band in 1..self.band_count().min(kernel.band_count()) {
+            let (ax, ay) = self.band(band);
+            let (bx, by) = kernel.band(band);
+            let x = T::PI.sqrt() * (ax * bx - ay * by);
+            let y = T::PI.sqrt() * (ax * by + ay * bx);
+            result.set_band(band, x, y);
+        }
 
-        let mut result_terms = vec!(0.0; result_term_count);
+        result
+    }
 
-        let h0 = other.coeffs.get(0).unwrap_or(&1.0);
+    // Band-limited product of two CH functions. The exact product of an N-band and an M-band CH
+    // has N + M - 1 bands, so passing that as band_count keeps everything; anything lower
+    // truncates the result.
+    pub fn product(&self, other: &CircularHarmonics<T>, band_count: usize) -> CircularHarmonics<T> {
+        // Work with plain Fourier series coefficients: f(θ) = Σ c[k]·cos(kθ) + s[k]·sin(kθ)
+        let to_fourier = |ch: &CircularHarmonics<T>| -> (Vec<T>, Vec<T>) {
+            let mut c = vec![T::ZERO; ch.band_count()];
+            let mut s = vec![T::ZERO; ch.band_count()];
+            c[0] = ch.coeffs[0] / (T::from_f64(2.0) * T::PI).sqrt();
+            for band in 1..ch.band_count() {
+                let (x, y) = ch.band(band);
+                c[band] = x / T::PI.sqrt();
+                s[band] = y / T::PI.sqrt();
+            }
+            (c, s)
+        };
 
-        for i in 0..result_term_count {
-            let a = self.coeffs.get(i).unwrap_or(&1.0);
+        let (ca, sa) = to_fourier(self);
+        let (cb, sb) = to_fourier(other);
 
-            result_terms[i] = a * h0;
+        let full_band_count = self.band_count() + other.band_count() - 1;
+        let mut c = vec![T::ZERO; full_band_count];
+        let mut s = vec![T::ZERO; full_band_count];
+
+        // Product-to-sum identities:
+        //   cos(j)cos(k) = (cos(j - k) + cos(j + k)) / 2
+        //   sin(j)sin(k) = (cos(j - k) - cos(j + k)) / 2
+        //   cos(j)sin(k) = (sin(j + k) - sin(j - k)) / 2
+        //   sin(j)cos(k) = (sin(j + k) + sin(j - k)) / 2
+        let half = T::from_f64(0.5);
+        for j in 0..ca.len() {
+            for k in 0..cb.len() {
+                let sum = j + k;
+                let diff = j as isize - k as isize;
+                let diff_sign = T::from_f64(diff.signum() as f64);
+                let diff = diff.unsigned_abs();
+
+                let cc = ca[j] * cb[k] * half;
+                let ss = sa[j] * sb[k] * half;
+                let cs = ca[j] * sb[k] * half;
+                let sc = sa[j] * cb[k] * half;
+
+                c[diff] += cc + ss;
+                c[sum] += cc - ss;
+
+                // sin(-x) = -sin(x), and sin(0) = 0
+                s[sum] += cs + sc;
+                s[diff] += (sc - cs) * diff_sign;
+            }
         }
 
-        CircularHarmonics {
-            coeffs: result_terms,
+        let mut result = Self::new(band_count);
+        result.coeffs[0] = c[0] * (T::from_f64(2.0) * T::PI).sqrt();
+        for band in 1..band_count.min(full_band_count) {
+            result.set_band(band, c[band] * T::PI.sqrt(), s[band] * T::PI.sqrt());
         }
+
+        result
     }
-    */
 }
 
-impl<'a, 'b> Add<&'b CircularHarmonics> for &'a CircularHarmonics {
-    type Output = CircularHarmonics;
+impl<'a, 'b, T: Scalar> Add<&'b CircularHarmonics<T>> for &'a CircularHarmonics<T> {
+    type Output = CircularHarmonics<T>;
 
-    fn add(self, other: &'b CircularHarmonics) -> CircularHarmonics {
+    fn add(self, other: &'b CircularHarmonics<T>) -> CircularHarmonics<T> {
         let result_term_count = self.coeff_count().max(other.coeff_count());
 
-        let mut result_terms = vec![0.0; result_term_count];
+        let mut result_terms = vec![T::ZERO; result_term_count];
 
         for i in 0..result_term_count {
-            let a = self.coeffs.get(i).unwrap_or(&0.0);
-            let b = other.coeffs.get(i).unwrap_or(&0.0);
+            let a = *self.coeffs.get(i).unwrap_or(&T::ZERO);
+            let b = *other.coeffs.get(i).unwrap_or(&T::ZERO);
 
             result_terms[i] = a + b;
         }
@@ -176,17 +1480,17 @@ impl<'a, 'b> Add<&'b CircularHarmonics> for &'a CircularHarmonics {
     }
 }
 
-impl<'a, 'b> Sub<&'b CircularHarmonics> for &'a CircularHarmonics {
-    type Output = CircularHarmonics;
+impl<'a, 'b, T: Scalar> Sub<&'b CircularHarmonics<T>> for &'a CircularHarmonics<T> {
+    type Output = CircularHarmonics<T>;
 
-    fn sub(self, other: &'b CircularHarmonics) -> CircularHarmonics {
+    fn sub(self, other: &'b CircularHarmonics<T>) -> CircularHarmonics<T> {
         let result_term_count = self.coeff_count().max(other.coeff_count());
 
-        let mut result_terms = vec![0.0; result_term_count];
+        let mut result_terms = vec![T::ZERO; result_term_count];
 
         for i in 0..result_term_count {
-            let a = self.coeffs.get(i).unwrap_or(&0.0);
-            let b = other.coeffs.get(i).unwrap_or(&0.0);
+            let a = *self.coeffs.get(i).unwrap_or(&T::ZERO);
+            let b = *other.coeffs.get(i).unwrap_or(&T::ZERO);
 
             result_terms[i] = a - b;
         }
@@ -196,3 +1500,636 @@ impl<'a, 'b> Sub<&'b CircularHarmonics> for &'a CircularHarmonics {
         }
     }
 }
+
+// Keeps the band count of the largest operand, like Add and Sub.
+// Use CircularHarmonics::product to get all bands of the product.
+impl<'b, T: Scalar> Mul<&'b CircularHarmonics<T>> for &CircularHarmonics<T> {
+    type Output = CircularHarmonics<T>;
+
+    fn mul(self, other: &'b CircularHarmonics<T>) -> CircularHarmonics<T> {
+        self.product(other, self.band_count().max(other.band_count()))
+    }
+}
+
+// Importance sampling of angles, using a non-negative CH as a (not necessarily normalized) PDF.
+// Uniform random numbers are mapped to angles by inverting the closed-form CDF.
+pub struct CircularHarmonicsSampler<T: Scalar = f32> {
+    pdf: CircularHarmonics<T>,
+    cdf: Cumulative<T>,
+}
+
+impl<T: Scalar> CircularHarmonicsSampler<T> {
+    // Returns None if the CH has negative values or integrates to 0,
+    // since then it can't be used as a distribution
+    pub fn new(ch: &CircularHarmonics<T>) -> Option<CircularHarmonicsSampler<T>> {
+        let total = ch.integral_between(T::ZERO, T::from_f64(2.0) * T::PI);
+        if total <= T::ZERO || ch.min_value() < -T::from_f64(1e-6) * total {
+            return None;
+        }
+
+        let pdf = CircularHarmonics::from_coeffs(ch.coeffs.iter().map(|&c| c / total).collect());
+        let cdf = pdf.cumulative();
+        Some(CircularHarmonicsSampler { pdf, cdf })
+    }
+
+    pub fn pdf(&self, angle: T) -> T {
+        self.pdf.evaluate(angle).max(T::ZERO)
+    }
+
+    // Maps u in [0, 1) to an angle in [0, 2π), returning (angle, pdf).
+    // Solves CDF(angle) = u with Newton iterations, falling back to bisection whenever a
+    // Newton step leaves the bracket that contains the solution.
+    pub fn sample(&self, u: T) -> (T, T) {
+        let two_pi = T::from_f64(2.0) * T::PI;
+        let (mut lo, mut hi) = (T::ZERO, two_pi);
+        let mut angle = u * two_pi;
+        for _ in 0..64 {
+            let error = self.cdf.evaluate(angle) - u;
+            if error.abs() < T::from_f64(1e-6) {
+                break;
+            }
+            if error < T::ZERO {
+                lo = angle;
+            } else {
+                hi = angle;
+            }
+
+            let pdf = self.pdf(angle);
+            let newton = angle - error / pdf;
+            angle = if pdf > T::ZERO && newton > lo && newton < hi {
+                newton
+            } else {
+                (lo + hi) * T::from_f64(0.5)
+            };
+        }
+
+        (angle, self.pdf(angle))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::PI;
+
+    // Riemann sum of ∫ f(φ)·g(θ - φ) dφ
+    fn brute_force_convolution(
+        f: &CircularHarmonics<f64>,
+        g: &CircularHarmonics<f64>,
+        angle: f64,
+    ) -> f64 {
+        let n = 4000;
+        let step = 2.0 * PI / n as f64;
+        (0..n)
+            .map(|i| {
+                let phi = i as f64 * step;
+                f.evaluate(phi) * g.evaluate(angle - phi) * step
+            })
+            .sum()
+    }
+
+    #[test]
+    fn project_reproduces_pulse() {
+        // The box edges fall exactly between Uniform samples, so only the smooth parts add error
+        let width = PI * 0.5;
+        let pulse = CircularHarmonics::from_pulse(16, width, 1.0, Normalization::Raw);
+        let angle_to_box = |angle: f64| {
+            let d = (angle + PI).rem_euclid(2.0 * PI) - PI;
+            if d.abs() < width * 0.5 {
+                1.0
+            } else {
+                0.0
+            }
+        };
+        let projected = CircularHarmonics::project(16, angle_to_box, 8000, Quadrature::Uniform);
+        assert!(
+            projected.distance(&pulse) < 1e-5,
+            "{}",
+            projected.distance(&pulse)
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn trapezoid_needs_two_samples() {
+        CircularHarmonics::<f64>::project(4, |a| a.cos(), 1, Quadrature::Trapezoid);
+    }
+
+    #[test]
+    fn gaussian_window_keeps_band0() {
+        let mut ch = CircularHarmonics::from_pulse(8, 1.0, 1.0, Normalization::Raw);
+        let band0 = ch.band0();
+        ch.apply_window(WindowKind::Gaussian(0.0));
+        assert_eq!(ch.band0(), band0);
+        assert_eq!(ch.band(3), (0.0, 0.0));
+    }
+
+    fn assert_nonnegative<T: Scalar>(ch: &CircularHarmonics<T>) {
+        for v in ch.evaluate_many(
+            &(0..4096)
+                .map(|i| T::from_usize(i) * T::from_f64(2.0 * PI / 4096.0))
+                .collect::<Vec<T>>(),
+        ) {
+            assert!(v >= T::ZERO, "{} < 0 with {} bands", v, ch.band_count());
+        }
+    }
+
+    #[test]
+    fn make_nonnegative_impulses() {
+        // Every small band count, and a few large ones so the test stays fast in debug builds
+        for band_count in (2..=16).chain([24, 32, 48, 64].iter().copied()) {
+            for &strength in &[1.0, -1.0, 1000.0] {
+                let angle = band_count as f64 * 0.37;
+                let mut ch = CircularHarmonics::<f64>::from_impulse(
+                    band_count,
+                    angle,
+                    strength,
+                    Normalization::Raw,
+                );
+                ch.make_nonnegative();
+                assert_nonnegative(&ch);
+
+                let mut ch = CircularHarmonics::<f32>::from_impulse(
+                    band_count,
+                    angle as f32,
+                    strength as f32,
+                    Normalization::Raw,
+                );
+                ch.make_nonnegative();
+                assert_nonnegative(&ch);
+            }
+        }
+    }
+
+    #[test]
+    fn make_nonnegative_lifts_as_little_as_possible() {
+        // Windowing can't fix a negative mean, so band 0 has to be raised. The result has to be
+        // at least as close to the original as just raising the original.
+        let mut original = CircularHarmonics::<f64>::new(8);
+        original.set_band0(-1.0);
+        original.set_band(3, 0.2, 0.1);
+        original.set_band(7, -0.1, 0.3);
+        let mut lifted = original.clone();
+        lifted.coeffs[0] -= original.min_value() * (2.0 * PI).sqrt();
+
+        let mut ch = original.clone();
+        ch.make_nonnegative();
+        assert_nonnegative(&ch);
+        assert!(ch.distance(&original) <= lifted.distance(&original) + 1e-12);
+    }
+
+    #[test]
+    fn sampler_chi_square() {
+        let ch = CircularHarmonics::<f64>::from_von_mises(16, 2.0, 3.0);
+        let sampler = CircularHarmonicsSampler::new(&ch).unwrap();
+        let total = ch.integral_between(0.0, 2.0 * PI);
+
+        // xorshift64, so the test is deterministic and needs no dependencies
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        let mut random = || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state >> 11) as f64 / (1u64 << 53) as f64
+        };
+
+        let (sample_count, bin_count) = (200_000, 32);
+        let mut counts = vec![0usize; bin_count];
+        for _ in 0..sample_count {
+            let (angle, pdf) = sampler.sample(random());
+            assert_eq!(pdf, sampler.pdf(angle));
+            let bin = (angle / (2.0 * PI) * bin_count as f64) as usize;
+            counts[bin.min(bin_count - 1)] += 1;
+        }
+
+        let bin_width = 2.0 * PI / bin_count as f64;
+        let chi_square: f64 = counts
+            .iter()
+            .enumerate()
+            .map(|(i, &count)| {
+                let (a, b) = (i as f64 * bin_width, (i + 1) as f64 * bin_width);
+                let expected = ch.integral_between(a, b) / total * sample_count as f64;
+                (count as f64 - expected).powi(2) / expected
+            })
+            .sum();
+        // 99.9th percentile of the χ² distribution with 31 degrees of freedom
+        assert!(chi_square < 61.1, "χ² = {}", chi_square);
+    }
+
+    #[test]
+    #[should_panic(expected = "at least 1 band")]
+    fn impulse_needs_a_band() {
+        CircularHarmonics::<f32>::from_impulse(0, 0.0, 1.0, Normalization::Raw);
+    }
+
+    #[test]
+    fn empty_coeffs_have_no_bands() {
+        assert_eq!(
+            CircularHarmonics::<f32>::try_from_coeffs(vec![]),
+            Err(ChError::NoBands)
+        );
+        assert_eq!(
+            CircularHarmonics::<f32>::try_from_coeffs(vec![0.0; 2]),
+            Err(ChError::EvenCoeffCount(2))
+        );
+    }
+
+    #[test]
+    fn relative_error_against_zero() {
+        let zero = CircularHarmonics::<f64>::new(4);
+        let one = CircularHarmonics::from_pulse(4, PI, 1.0, Normalization::Raw);
+        assert_eq!(zero.relative_error(&zero), 0.0);
+        assert_eq!(one.relative_error(&zero), f64::INFINITY);
+        assert_eq!(zero.relative_error(&one), 1.0);
+    }
+
+    #[test]
+    fn approximation_error_sees_the_jump() {
+        // Any truncated series has to miss a jump of 1 by about half of it
+        let f = |angle: f64| if angle < PI { 1.0 } else { 0.0 };
+        let error = CircularHarmonics::approximation_error(f, 16);
+        assert!(error.linf >= 0.49, "{}", error.linf);
+    }
+
+    fn assert_matches_projection(ch: CircularHarmonics<f64>, f: impl Fn(f64) -> f64) {
+        let projected = CircularHarmonics::project(ch.band_count(), f, 20000, Quadrature::Uniform);
+        for (a, b) in ch.coeffs.iter().zip(projected.coeffs.iter()) {
+            assert!((a - b).abs() < 1e-6, "{} != {}", a, b);
+        }
+    }
+
+    #[test]
+    fn lobes_match_projection() {
+        let center = 0.7;
+        // Signed distance to the center, in [-π, π)
+        let offset = move |angle: f64| (angle - center + PI).rem_euclid(2.0 * PI) - PI;
+
+        assert_matches_projection(
+            CircularHarmonics::from_clamped_cosine(12, center),
+            |angle| offset(angle).cos().max(0.0),
+        );
+        for &kappa in &[0.0, 0.5, 8.0, 100.0] {
+            assert_matches_projection(
+                CircularHarmonics::from_von_mises(12, center, kappa),
+                |angle| (kappa * (offset(angle).cos() - 1.0)).exp(),
+            );
+        }
+        for &half_width in &[0.3, 1.0, PI] {
+            assert_matches_projection(
+                CircularHarmonics::from_tent(12, center, half_width),
+                |angle| (1.0 - offset(angle).abs() / half_width).max(0.0),
+            );
+            assert_matches_projection(
+                CircularHarmonics::from_raised_cosine(12, center, half_width),
+                |angle| {
+                    if offset(angle).abs() < half_width {
+                        0.5 * (1.0 + (PI * offset(angle) / half_width).cos())
+                    } else {
+                        0.0
+                    }
+                },
+            );
+        }
+        for &exponent in &[0.5, 1.0, 2.5, 40.0] {
+            assert_matches_projection(
+                CircularHarmonics::from_phong(12, center, exponent),
+                |angle| offset(angle).cos().max(0.0).powf(exponent),
+            );
+        }
+    }
+
+    fn assert_normalized(ch: &CircularHarmonics<f64>, normalization: Normalization, strength: f64) {
+        let value = match normalization {
+            Normalization::Raw | Normalization::UnitIntegral => ch.band0() * (2.0 * PI).sqrt(),
+            Normalization::UnitPeak => ch.max_value().max(-ch.min_value()),
+            Normalization::UnitEnergy => ch.norm(),
+        };
+        let expected = match normalization {
+            Normalization::Raw | Normalization::UnitIntegral => strength,
+            _ => strength.abs(),
+        };
+        assert!(
+            (value - expected).abs() < 1e-9,
+            "{:?}: {} != {}",
+            normalization,
+            value,
+            expected
+        );
+    }
+
+    #[test]
+    fn normalization_modes() {
+        let modes = [
+            Normalization::Raw,
+            Normalization::UnitIntegral,
+            Normalization::UnitPeak,
+            Normalization::UnitEnergy,
+        ];
+        for &normalization in &modes {
+            for &strength in &[2.0, -0.5] {
+                let impulse = CircularHarmonics::from_impulse(8, 1.0, strength, normalization);
+                assert_normalized(&impulse, normalization, strength);
+                assert_eq!(impulse.evaluate(1.0).signum(), strength.signum());
+
+                // With Raw, strength is the height of the box, so the integral is scaled by the
+                // width
+                let width = 0.5;
+                let pulse = CircularHarmonics::from_pulse(8, width, strength, normalization);
+                match normalization {
+                    Normalization::Raw => {
+                        assert_normalized(&pulse, Normalization::UnitIntegral, strength * width)
+                    }
+                    _ => assert_normalized(&pulse, normalization, strength),
+                }
+                assert_eq!(pulse.evaluate(0.0).signum(), strength.signum());
+            }
+        }
+    }
+
+    #[test]
+    fn fit_lobes_stops_at_exact_fit() {
+        let kind = LobeKind::VonMises { kappa: 8.0 };
+        let lobes = CircularHarmonics::<f32>::from_von_mises(12, 0.5, 8.0).fit_lobes(3, kind);
+        assert_eq!(lobes.len(), 1);
+        assert!((lobes[0].direction - 0.5).abs() < 1e-3);
+        assert!((lobes[0].amplitude - 1.0).abs() < 1e-3);
+
+        let kind = LobeKind::VonMises { kappa: 8.0 };
+        let lobes = CircularHarmonics::<f64>::from_von_mises(12, 0.5, 8.0).fit_lobes(3, kind);
+        assert_eq!(lobes.len(), 1);
+    }
+
+    // Irregularly spaced angles, so fits can't rely on a uniform grid
+    fn irregular_angles(count: usize) -> Vec<f64> {
+        (0..count)
+            .map(|i| i as f64 * 2.0 * PI / count as f64 + 0.3 * (i as f64 * 1.7).sin())
+            .collect()
+    }
+
+    #[test]
+    fn least_squares_recovers_exact_ch() {
+        let ch = CircularHarmonics::from_coeffs(vec![0.5, 1.0, -0.3, 0.2, 0.7, -0.4, 0.1]);
+        let angles = irregular_angles(ch.coeff_count());
+        let values = ch.evaluate_many(&angles);
+        let fit =
+            CircularHarmonics::fit_least_squares(&angles, &values, None, 4, Regularization::None)
+                .unwrap();
+        for (a, b) in fit.ch.coeffs.iter().zip(ch.coeffs.iter()) {
+            assert!((a - b).abs() < 1e-9, "{} != {}", a, b);
+        }
+        assert!(fit.max_abs_error < 1e-9);
+    }
+
+    #[test]
+    fn least_squares_errors() {
+        let angles = irregular_angles(4);
+        let values = vec![1.0; 4];
+        let fit = |values: &[f64], weights: Option<&[f64]>, band_count: usize| {
+            CircularHarmonics::fit_least_squares(
+                &angles,
+                values,
+                weights,
+                band_count,
+                Regularization::None,
+            )
+            .err()
+        };
+        assert_eq!(fit(&values, None, 0), Some(ChError::NoBands));
+        assert_eq!(fit(&values, None, 3), Some(ChError::SingularSystem));
+        assert_eq!(
+            fit(&values[..3], None, 2),
+            Some(ChError::LengthMismatch {
+                expected: 4,
+                actual: 3
+            })
+        );
+        assert_eq!(
+            fit(&values, Some(&[1.0; 5]), 2),
+            Some(ChError::LengthMismatch {
+                expected: 4,
+                actual: 5
+            })
+        );
+        assert_eq!(fit(&values, Some(&[1.0; 4]), 2), None);
+    }
+
+    #[test]
+    fn least_squares_regularization_shrinks_high_bands() {
+        let mut ch = CircularHarmonics::<f64>::new(6);
+        ch.set_band(1, 1.0, 0.0);
+        ch.set_band(5, 1.0, 0.0);
+        let angles = irregular_angles(64);
+        let values = ch.evaluate_many(&angles);
+        let fit = |regularization| {
+            let fit =
+                CircularHarmonics::fit_least_squares(&angles, &values, None, 6, regularization)
+                    .unwrap();
+            (fit.ch.band(1).0, fit.ch.band(5).0)
+        };
+
+        let (low, high) = fit(Regularization::None);
+        assert!((low - 1.0).abs() < 1e-9 && (high - 1.0).abs() < 1e-9);
+
+        // Tikhonov shrinks every band, and Laplacian shrinks band 5 much more than band 1
+        let (low, high) = fit(Regularization::Tikhonov(10.0));
+        assert!(low < 0.99 && high < 0.99);
+        let (low, high) = fit(Regularization::Laplacian(0.1));
+        assert!(high < 0.5 && low > 0.99, "{} {}", low, high);
+    }
+
+    fn assert_coeffs_eq(a: &CircularHarmonics<f64>, b: &CircularHarmonics<f64>, tolerance: f64) {
+        assert_eq!(a.coeff_count(), b.coeff_count());
+        for (x, y) in a.coeffs.iter().zip(b.coeffs.iter()) {
+            assert!(
+                (x - y).abs() < tolerance,
+                "{:?} != {:?}",
+                a.coeffs,
+                b.coeffs
+            );
+        }
+    }
+
+    #[test]
+    fn to_samples_matches_evaluate() {
+        let ch =
+            CircularHarmonics::from_coeffs(vec![0.5, 1.0, -0.3, 0.2, 0.7, -0.4, 0.1, 0.8, 0.6]);
+        // Power of two, odd and even sizes, including ones too small for the 5 bands
+        for &n in &[1, 2, 3, 4, 7, 8, 9, 12, 16, 30, 64, 100] {
+            let samples = ch.to_samples(n);
+            assert_eq!(samples.len(), n);
+            for (j, &sample) in samples.iter().enumerate() {
+                let angle = j as f64 * 2.0 * PI / n as f64;
+                assert!((sample - ch.evaluate(angle)).abs() < 1e-9, "n = {}", n);
+            }
+        }
+    }
+
+    #[test]
+    fn from_samples_round_trip() {
+        let ch =
+            CircularHarmonics::from_coeffs(vec![0.5, 1.0, -0.3, 0.2, 0.7, -0.4, 0.1, 0.8, 0.6]);
+        for &n in &[9, 10, 15, 16, 24, 64, 100] {
+            assert_coeffs_eq(
+                &CircularHarmonics::from_samples(&ch.to_samples(n), 5),
+                &ch,
+                1e-9,
+            );
+        }
+
+        // With n = 8, band 4 is the Nyquist band. Its sine is 0 at every sample, so only the
+        // cosine survives.
+        let mut nyquist = ch.clone();
+        nyquist.set_band(4, 0.8, 0.0);
+        assert_coeffs_eq(
+            &CircularHarmonics::from_samples(&ch.to_samples(8), 5),
+            &nyquist,
+            1e-9,
+        );
+    }
+
+    #[test]
+    fn from_samples_aliases() {
+        // At 8 samples, cos(5θ) = cos(3θ) and sin(5θ) = -sin(3θ)
+        let mut ch = CircularHarmonics::<f64>::new(6);
+        ch.set_band(5, 0.7, 0.4);
+        let mut aliased = CircularHarmonics::new(4);
+        aliased.set_band(3, 0.7, -0.4);
+        assert_coeffs_eq(
+            &CircularHarmonics::from_samples(&ch.to_samples(8), 4),
+            &aliased,
+            1e-9,
+        );
+    }
+
+    // Angles on a dense uniform grid, and the function's values there
+    fn dense_samples(ch: &CircularHarmonics<f64>) -> (Vec<f64>, Vec<f64>) {
+        let angles: Vec<f64> = (0..20000).map(|i| i as f64 * 2.0 * PI / 20000.0).collect();
+        let values = ch.evaluate_many(&angles);
+        (angles, values)
+    }
+
+    // Midpoints of the grid intervals (wrapping around) where the function changes sign
+    fn sign_changes(ch: &CircularHarmonics<f64>) -> Vec<f64> {
+        let (angles, values) = dense_samples(ch);
+        let n = angles.len();
+        (0..n)
+            .filter(|&i| (values[i] < 0.0) != (values[(i + 1) % n] < 0.0))
+            .map(|i| angles[i] + PI / n as f64)
+            .collect()
+    }
+
+    // Angles just below 2π can match ones just above 0, so the order isn't compared
+    fn assert_angles_close(a: &[f64], b: &[f64], tolerance: f64) {
+        assert_eq!(a.len(), b.len(), "{:?} != {:?}", a, b);
+        for x in a {
+            assert!(
+                b.iter().any(|y| {
+                    let d = (x - y).abs();
+                    d.min(2.0 * PI - d) < tolerance
+                }),
+                "{:?} != {:?}",
+                a,
+                b
+            );
+        }
+    }
+
+    #[test]
+    fn roots_match_sign_changes() {
+        let ch =
+            CircularHarmonics::from_coeffs(vec![0.3, 1.0, -0.3, 0.2, 0.7, -0.4, 0.1, 0.8, 0.6]);
+        let roots = ch.roots();
+        assert_angles_close(&roots, &sign_changes(&ch), 2.0 * PI / 20000.0);
+        for &root in &roots {
+            assert!(ch.evaluate(root).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn roots_double() {
+        // 1 - cos(θ) touches 0 at θ = 0 without crossing it
+        let mut ch = CircularHarmonics::<f64>::new(3);
+        ch.set_band0((2.0 * PI).sqrt());
+        ch.set_band(1, -PI.sqrt(), 0.0);
+        assert_angles_close(&ch.roots(), &[0.0], 1e-4);
+
+        // (1 - cos(θ))·(1 + cos(θ)) = sin²(θ) touches 0 at 0 and π
+        let product = ch.product(&ch.rotate(PI), 3);
+        assert_angles_close(&product.roots(), &[0.0, PI], 1e-4);
+    }
+
+    #[test]
+    fn roots_of_constants() {
+        assert!(CircularHarmonics::<f64>::new(1).roots().is_empty());
+        assert!(CircularHarmonics::<f64>::new(5).roots().is_empty());
+
+        let mut ch = CircularHarmonics::<f64>::new(5);
+        ch.set_band0(1.0);
+        assert!(ch.roots().is_empty());
+        assert!(CircularHarmonics::from_coeffs(vec![-2.0])
+            .roots()
+            .is_empty());
+    }
+
+    #[test]
+    fn extrema_match_dense_sampling() {
+        let ch =
+            CircularHarmonics::from_coeffs(vec![0.3, 1.0, -0.3, 0.2, 0.7, -0.4, 0.1, 0.8, 0.6]);
+        let (_, values) = dense_samples(&ch);
+        let min = values.iter().cloned().fold(f64::INFINITY, f64::min);
+        let max = values.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        assert!(ch.min_value() <= min && ch.min_value() > min - 1e-6);
+        assert!(ch.max_value() >= max && ch.max_value() < max + 1e-6);
+        assert!((ch.evaluate(ch.argmin()) - ch.min_value()).abs() < 1e-12);
+        assert!((ch.evaluate(ch.argmax()) - ch.max_value()).abs() < 1e-12);
+
+        let extrema = ch.extrema();
+        assert_angles_close(
+            &extrema,
+            &sign_changes(&ch.derivative(1)),
+            2.0 * PI / 20000.0,
+        );
+    }
+
+    #[test]
+    fn arcs_match_rotated_pulses() {
+        let pulse = |width: f64, center: f64| {
+            CircularHarmonics::from_pulse(10, width, 2.0, Normalization::Raw).rotate(center)
+        };
+        let arc = |start: f64, end: f64| CircularHarmonics::from_arc(10, start, end, 2.0);
+
+        assert_coeffs_eq(&arc(0.5, 2.0), &pulse(1.5, 1.25), 1e-12);
+        // Crossing 2π
+        let width = 1.0 - 5.5 + 2.0 * PI;
+        assert_coeffs_eq(&arc(5.5, 1.0), &pulse(width, 5.5 + width * 0.5), 1e-12);
+        // A full turn or more, in either order
+        let full = pulse(2.0 * PI, 0.0);
+        assert_coeffs_eq(&arc(1.0, 1.0 + 2.0 * PI), &full, 1e-12);
+        assert_coeffs_eq(&arc(1.0, 8.0), &full, 1e-12);
+        assert_coeffs_eq(&arc(8.0, 1.0), &full, 1e-12);
+        assert_coeffs_eq(&arc(-20.0, 0.0), &full, 1e-12);
+    }
+
+    #[test]
+    fn overlapping_arcs_add_up() {
+        let ch =
+            CircularHarmonics::from_piecewise_constant(10, &[(0.0, 2.0, 1.0), (1.0, 3.0, 2.0)]);
+        let steps = CircularHarmonics::from_piecewise_constant(
+            10,
+            &[(0.0, 1.0, 1.0), (1.0, 2.0, 3.0), (2.0, 3.0, 2.0)],
+        );
+        assert_coeffs_eq(&ch, &steps, 1e-12);
+    }
+
+    #[test]
+    fn convolve_matches_brute_force() {
+        let f = CircularHarmonics::from_pulse(12, PI * 0.5, 1.0, Normalization::Raw).rotate(0.3);
+        let g = CircularHarmonics::from_pulse(8, PI * 0.25, 2.0, Normalization::Raw).rotate(2.0);
+        let convolved = f.convolve(&g);
+        for i in 0..32 {
+            let angle = i as f64 * 2.0 * PI / 32.0;
+            let expected = brute_force_convolution(&f, &g.with_band_count(12), angle);
+            assert!((convolved.evaluate(angle) - expected).abs() < 1e-9);
+        }
+    }
+}
@@ -0,0 +1,169 @@
+// Many CH with the same band count, stored as a structure of arrays: all the values of a given
+// coefficient are contiguous. Evaluating all of them at one angle means computing the basis
+// once, and then the per coefficient loops are plain multiply-adds over slices, which the
+// compiler can vectorize.
+
+use crate::circ_harmonics::{
+    calculate_term, check_band_count, term_to_band, ChError, CircularHarmonics,
+};
+use crate::scalar::Scalar;
+use std::ops::Add;
+
+pub struct CircularHarmonicsBatch<T: Scalar = f32> {
+    len: usize,
+    band_count: usize,
+    // Coefficient c of CH i is at coeffs[c * len + i]
+    coeffs: Vec<T>,
+}
+
+impl<T: Scalar> CircularHarmonicsBatch<T> {
+    pub fn new(band_count: usize, len: usize) -> CircularHarmonicsBatch<T> {
+        check_band_count(band_count).unwrap_or_else(|e| panic!("{}", e));
+        CircularHarmonicsBatch {
+            len,
+            band_count,
+            coeffs: vec![T::ZERO; (band_count * 2 - 1) * len],
+        }
+    }
+
+    // CH with fewer bands than the largest one get zero-extended
+    pub fn from_ch(chs: &[CircularHarmonics<T>]) -> CircularHarmonicsBatch<T> {
+        let band_count = chs.iter().map(|ch| ch.band_count()).max().unwrap_or(1);
+        let mut result = Self::new(band_count, chs.len());
+        for (i, ch) in chs.iter().enumerate() {
+            result.set(i, ch);
+        }
+
+        result
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn band_count(&self) -> usize {
+        self.band_count
+    }
+
+    pub fn coeff_count(&self) -> usize {
+        self.band_count * 2 - 1
+    }
+
+    fn coeff_row(&self, coeff: usize) -> &[T] {
+        &self.coeffs[coeff * self.len..(coeff + 1) * self.len]
+    }
+
+    pub fn get(&self, i: usize) -> CircularHarmonics<T> {
+        self.try_get(i).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_get(&self, i: usize) -> Result<CircularHarmonics<T>, ChError> {
+        self.check_index(i)?;
+        Ok(CircularHarmonics::from_coeffs(
+            (0..self.coeff_count())
+                .map(|c| self.coeffs[c * self.len + i])
+                .collect(),
+        ))
+    }
+
+    // Bands the batch doesn't have are dropped, and missing ones are set to 0
+    pub fn set(&mut self, i: usize, ch: &CircularHarmonics<T>) {
+        self.try_set(i, ch).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_set(&mut self, i: usize, ch: &CircularHarmonics<T>) -> Result<(), ChError> {
+        self.check_index(i)?;
+        for c in 0..self.coeff_count() {
+            self.coeffs[c * self.len + i] = match term_to_band(c) {
+                0 => ch.band0(),
+                band if band < ch.band_count() && c % 2 == 1 => ch.band(band).0,
+                band if band < ch.band_count() => ch.band(band).1,
+                _ => T::ZERO,
+            };
+        }
+        Ok(())
+    }
+
+    // An index past the end would land in the next coefficient's row
+    fn check_index(&self, i: usize) -> Result<(), ChError> {
+        if i >= self.len {
+            return Err(ChError::IndexOutOfRange {
+                index: i,
+                len: self.len,
+            });
+        }
+        Ok(())
+    }
+
+    // Evaluates every CH in the batch at the same angle
+    pub fn evaluate(&self, angle: T) -> Vec<T> {
+        let mut result = vec![T::ZERO; self.len];
+        self.evaluate_into(angle, &mut result);
+        result
+    }
+
+    pub fn evaluate_into(&self, angle: T, result: &mut [T]) {
+        assert_eq!(result.len(), self.len);
+        for v in result.iter_mut() {
+            *v = T::ZERO;
+        }
+
+        for c in 0..self.coeff_count() {
+            let term = calculate_term(c, angle);
+            for (v, &coeff) in result.iter_mut().zip(self.coeff_row(c)) {
+                *v += term * coeff;
+            }
+        }
+    }
+
+    pub fn rotate(&self, angle: T) -> CircularHarmonicsBatch<T> {
+        let mut result = Self::new(self.band_count, self.len);
+        result.coeffs[..self.len].copy_from_slice(self.coeff_row(0));
+
+        for band in 1..self.band_count {
+            let (s, c) = (angle * T::from_usize(band)).sin_cos();
+            let (rot_x, rot_y) = result.coeffs
+                [(band * 2 - 1) * self.len..(band * 2 + 1) * self.len]
+                .split_at_mut(self.len);
+            let x = self.coeff_row(band * 2 - 1);
+            let y = self.coeff_row(band * 2);
+            for i in 0..self.len {
+                rot_x[i] = x[i] * c - y[i] * s;
+                rot_y[i] = y[i] * c + x[i] * s;
+            }
+        }
+
+        result
+    }
+}
+
+// Both batches need the same length. Like CircularHarmonics' Add, the result has the band count
+// of the largest operand.
+impl<'b, T: Scalar> Add<&'b CircularHarmonicsBatch<T>> for &CircularHarmonicsBatch<T> {
+    type Output = CircularHarmonicsBatch<T>;
+
+    fn add(self, other: &'b CircularHarmonicsBatch<T>) -> CircularHarmonicsBatch<T> {
+        assert_eq!(self.len, other.len);
+
+        let mut result =
+            CircularHarmonicsBatch::new(self.band_count.max(other.band_count), self.len);
+        for (c, row) in result.coeffs.chunks_exact_mut(self.len.max(1)).enumerate() {
+            if c < self.coeff_count() {
+                for (r, &a) in row.iter_mut().zip(self.coeff_row(c)) {
+                    *r += a;
+                }
+            }
+            if c < other.coeff_count() {
+                for (r, &b) in row.iter_mut().zip(other.coeff_row(c)) {
+                    *r += b;
+                }
+            }
+        }
+
+        result
+    }
+}
@@ -0,0 +1,241 @@
+// Fixed band count version of CircularHarmonics. Coefficients live in arrays, so it never
+// allocates and can be copied around freely, which matters when creating lots of them.
+
+use crate::circ_harmonics::{check_band_count, ChError, CircularHarmonics};
+use crate::scalar::Scalar;
+use std::convert::TryFrom;
+use std::ops::{Add, Mul, Sub};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CircularHarmonicsN<const BANDS: usize, T: Scalar = f32> {
+    // Band k is (x[k], y[k]). Band 0 only uses x[0], and y[0] is always 0
+    x: [T; BANDS],
+    y: [T; BANDS],
+}
+
+fn check_band(n: usize, band_count: usize) -> Result<(), ChError> {
+    if n >= band_count {
+        return Err(ChError::BandOutOfRange {
+            band: n,
+            band_count,
+        });
+    }
+
+    if n == 0 {
+        return Err(ChError::Band0NotAccessible);
+    }
+    Ok(())
+}
+
+impl<const BANDS: usize, T: Scalar> CircularHarmonicsN<BANDS, T> {
+    pub fn new() -> CircularHarmonicsN<BANDS, T> {
+        check_band_count(BANDS).unwrap_or_else(|e| panic!("{}", e));
+        CircularHarmonicsN {
+            x: [T::ZERO; BANDS],
+            y: [T::ZERO; BANDS],
+        }
+    }
+
+    pub fn coeff_count(&self) -> usize {
+        BANDS * 2 - 1
+    }
+
+    pub fn band_count(&self) -> usize {
+        BANDS
+    }
+
+    // Same angle addition recurrence as CircularHarmonics::evaluate, so only one sin_cos
+    pub fn evaluate(&self, angle: T) -> T {
+        let (s, c) = angle.sin_cos();
+        let (mut band_s, mut band_c) = (T::ZERO, T::ONE);
+        let mut bands = T::ZERO;
+        for band in 1..BANDS {
+            let next_c = band_c * c - band_s * s;
+            let next_s = band_s * c + band_c * s;
+            band_c = next_c;
+            band_s = next_s;
+            bands += self.x[band] * band_c + self.y[band] * band_s;
+        }
+
+        self.x[0] / (T::from_f64(2.0) * T::PI).sqrt() + bands / T::PI.sqrt()
+    }
+
+    pub fn rotate(&self, angle: T) -> CircularHarmonicsN<BANDS, T> {
+        let mut result = *self;
+        for band in 1..BANDS {
+            let (s, c) = (angle * T::from_usize(band)).sin_cos();
+            let (bandx, bandy) = (self.x[band], self.y[band]);
+            result.x[band] = bandx * c - bandy * s;
+            result.y[band] = bandy * c + bandx * s;
+        }
+
+        result
+    }
+
+    pub fn band(&self, n: usize) -> (T, T) {
+        self.try_band(n).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_band(&self, n: usize) -> Result<(T, T), ChError> {
+        check_band(n, BANDS)?;
+        Ok((self.x[n], self.y[n]))
+    }
+
+    pub fn band0(&self) -> T {
+        self.x[0]
+    }
+
+    pub fn set_band(&mut self, n: usize, a: T, b: T) {
+        self.try_set_band(n, a, b)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_set_band(&mut self, n: usize, a: T, b: T) -> Result<(), ChError> {
+        check_band(n, BANDS)?;
+        self.x[n] = a;
+        self.y[n] = b;
+        Ok(())
+    }
+
+    pub fn set_band0(&mut self, v: T) {
+        self.x[0] = v;
+    }
+}
+
+impl<const BANDS: usize, T: Scalar> Default for CircularHarmonicsN<BANDS, T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const BANDS: usize, T: Scalar> Add for CircularHarmonicsN<BANDS, T> {
+    type Output = CircularHarmonicsN<BANDS, T>;
+
+    fn add(self, other: CircularHarmonicsN<BANDS, T>) -> CircularHarmonicsN<BANDS, T> {
+        let mut result = self;
+        for band in 0..BANDS {
+            result.x[band] += other.x[band];
+            result.y[band] += other.y[band];
+        }
+
+        result
+    }
+}
+
+impl<const BANDS: usize, T: Scalar> Sub for CircularHarmonicsN<BANDS, T> {
+    type Output = CircularHarmonicsN<BANDS, T>;
+
+    fn sub(self, other: CircularHarmonicsN<BANDS, T>) -> CircularHarmonicsN<BANDS, T> {
+        let mut result = self;
+        for band in 0..BANDS {
+            result.x[band] -= other.x[band];
+            result.y[band] -= other.y[band];
+        }
+
+        result
+    }
+}
+
+// Band-limited product, truncated to BANDS bands like CircularHarmonics' Mul.
+// See CircularHarmonics::product for the identities used.
+impl<const BANDS: usize, T: Scalar> Mul for CircularHarmonicsN<BANDS, T> {
+    type Output = CircularHarmonicsN<BANDS, T>;
+
+    fn mul(self, other: CircularHarmonicsN<BANDS, T>) -> CircularHarmonicsN<BANDS, T> {
+        // Plain Fourier series coefficients, so band 0 gets scaled like the rest
+        let band0_scale = T::ONE / (T::from_f64(2.0) * T::PI).sqrt();
+        let band_scale = T::ONE / T::PI.sqrt();
+        let to_fourier = |ch: &CircularHarmonicsN<BANDS, T>| {
+            let mut c = [T::ZERO; BANDS];
+            let mut s = [T::ZERO; BANDS];
+            for band in 0..BANDS {
+                let scale = if band == 0 { band0_scale } else { band_scale };
+                c[band] = ch.x[band] * scale;
+                s[band] = ch.y[band] * scale;
+            }
+            (c, s)
+        };
+        let (ca, sa) = to_fourier(&self);
+        let (cb, sb) = to_fourier(&other);
+
+        let mut c = [T::ZERO; BANDS];
+        let mut s = [T::ZERO; BANDS];
+        let half = T::from_f64(0.5);
+        for j in 0..BANDS {
+            for k in 0..BANDS {
+                let cc = ca[j] * cb[k] * half;
+                let ss = sa[j] * sb[k] * half;
+                let cs = ca[j] * sb[k] * half;
+                let sc = sa[j] * cb[k] * half;
+
+                let diff = j.abs_diff(k);
+                c[diff] += cc + ss;
+                if j > k {
+                    s[diff] += sc - cs;
+                } else if k > j {
+                    s[diff] -= sc - cs;
+                }
+
+                if j + k < BANDS {
+                    c[j + k] += cc - ss;
+                    s[j + k] += cs + sc;
+                }
+            }
+        }
+
+        let mut result = Self::new();
+        for band in 0..BANDS {
+            let scale = if band == 0 { band0_scale } else { band_scale };
+            result.x[band] = c[band] / scale;
+            result.y[band] = if band == 0 { T::ZERO } else { s[band] / scale };
+        }
+
+        result
+    }
+}
+
+impl<const BANDS: usize, T: Scalar> From<CircularHarmonicsN<BANDS, T>> for CircularHarmonics<T> {
+    fn from(ch: CircularHarmonicsN<BANDS, T>) -> CircularHarmonics<T> {
+        let mut result = CircularHarmonics::new(BANDS);
+        result.set_band0(ch.x[0]);
+        for band in 1..BANDS {
+            result.set_band(band, ch.x[band], ch.y[band]);
+        }
+
+        result
+    }
+}
+
+// Only succeeds when the band counts match
+impl<const BANDS: usize, T: Scalar> TryFrom<&CircularHarmonics<T>>
+    for CircularHarmonicsN<BANDS, T>
+{
+    type Error = ChError;
+
+    fn try_from(ch: &CircularHarmonics<T>) -> Result<Self, Self::Error> {
+        if ch.band_count() != BANDS {
+            return Err(ChError::BandCountMismatch {
+                expected: BANDS,
+                actual: ch.band_count(),
+            });
+        }
+
+        let mut result = Self::new();
+        result.x[0] = ch.band0();
+        for band in 1..BANDS {
+            let (x, y) = ch.band(band);
+            result.x[band] = x;
+            result.y[band] = y;
+        }
+
+        Ok(result)
+    }
+}
+
+impl<const BANDS: usize, T: Scalar> TryFrom<CircularHarmonics<T>> for CircularHarmonicsN<BANDS, T> {
+    type Error = ChError;
+
+    fn try_from(ch: CircularHarmonics<T>) -> Result<Self, Self::Error> {
+        Self::try_from(&ch)
+    }
+}
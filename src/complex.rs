@@ -0,0 +1,92 @@
+use crate::scalar::Scalar;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Complex<T: Scalar = f32> {
+    pub re: T,
+    pub im: T,
+}
+
+impl<T: Scalar> Complex<T> {
+    pub fn new(re: T, im: T) -> Complex<T> {
+        Complex { re, im }
+    }
+
+    // e^(iθ)
+    pub fn from_angle(angle: T) -> Complex<T> {
+        let (s, c) = angle.sin_cos();
+        Complex { re: c, im: s }
+    }
+
+    pub fn conj(self) -> Complex<T> {
+        Complex::new(self.re, -self.im)
+    }
+
+    pub fn scale(self, s: T) -> Complex<T> {
+        Complex::new(self.re * s, self.im * s)
+    }
+
+    pub fn norm_sqr(self) -> T {
+        self.re * self.re + self.im * self.im
+    }
+
+    pub fn abs(self) -> T {
+        self.norm_sqr().sqrt()
+    }
+
+    pub fn arg(self) -> T {
+        self.im.atan2(self.re)
+    }
+
+    // Principal square root
+    pub fn sqrt(self) -> Complex<T> {
+        let r = self.abs();
+        let half = T::from_f64(0.5);
+        let re = ((r + self.re) * half).max(T::ZERO).sqrt();
+        let im = ((r - self.re) * half).max(T::ZERO).sqrt();
+        Complex::new(re, if self.im < T::ZERO { -im } else { im })
+    }
+}
+
+impl<T: Scalar> Add for Complex<T> {
+    type Output = Complex<T>;
+
+    fn add(self, other: Complex<T>) -> Complex<T> {
+        Complex::new(self.re + other.re, self.im + other.im)
+    }
+}
+
+impl<T: Scalar> Sub for Complex<T> {
+    type Output = Complex<T>;
+
+    fn sub(self, other: Complex<T>) -> Complex<T> {
+        Complex::new(self.re - other.re, self.im - other.im)
+    }
+}
+
+impl<T: Scalar> Mul for Complex<T> {
+    type Output = Complex<T>;
+
+    fn mul(self, other: Complex<T>) -> Complex<T> {
+        Complex::new(
+            self.re * other.re - self.im * other.im,
+            self.re * other.im + self.im * other.re,
+        )
+    }
+}
+
+impl<T: Scalar> Div for Complex<T> {
+    type Output = Complex<T>;
+
+    fn div(self, other: Complex<T>) -> Complex<T> {
+        (self * other.conj()).scale(T::ONE / other.norm_sqr())
+    }
+}
+
+impl<T: Scalar> Neg for Complex<T> {
+    type Output = Complex<T>;
+
+    fn neg(self) -> Complex<T> {
+        Complex::new(-self.re, -self.im)
+    }
+}
@@ -0,0 +1,234 @@
+// CH written as a complex Fourier series, f(θ) = Σ c[k]·e^(ikθ) for |k| < band count. This is
+// the form most DSP code uses. For a CH band k with coefficients (x, y):
+//   c[k] = (x - iy) / (2·√π),  c[-k] = (x + iy) / (2·√π)
+// and for band 0, c[0] = band0 / √(2π). Real functions have c[-k] = conj(c[k]).
+
+use crate::circ_harmonics::{check_band_count, ChError, CircularHarmonics};
+use crate::complex::Complex;
+use crate::scalar::Scalar;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ComplexHarmonics<T: Scalar = f32> {
+    // c[k] is at coeffs[k + band_count - 1], so k goes from -(band_count - 1) to band_count - 1
+    coeffs: Vec<Complex<T>>,
+}
+
+impl<T: Scalar> ComplexHarmonics<T> {
+    pub fn new(band_count: usize) -> ComplexHarmonics<T> {
+        check_band_count(band_count).unwrap_or_else(|e| panic!("{}", e));
+        ComplexHarmonics {
+            coeffs: vec![Complex::default(); band_count * 2 - 1],
+        }
+    }
+
+    // Takes c[-K], ..., c[0], ..., c[K] in that order, for band count K + 1
+    pub fn from_coeffs(coeffs: Vec<Complex<T>>) -> ComplexHarmonics<T> {
+        Self::try_from_coeffs(coeffs).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_from_coeffs(coeffs: Vec<Complex<T>>) -> Result<ComplexHarmonics<T>, ChError> {
+        if coeffs.is_empty() {
+            return Err(ChError::NoBands);
+        }
+        if coeffs.len() % 2 != 1 {
+            return Err(ChError::EvenCoeffCount(coeffs.len()));
+        }
+
+        Ok(ComplexHarmonics { coeffs })
+    }
+
+    // c[-K], ..., c[0], ..., c[K], the same layout from_coeffs takes
+    pub fn coeffs(&self) -> &[Complex<T>] {
+        &self.coeffs
+    }
+
+    pub fn from_ch(ch: &CircularHarmonics<T>) -> ComplexHarmonics<T> {
+        let mut result = Self::new(ch.band_count());
+        let inv_2_sqrt_pi = T::ONE / (T::from_f64(2.0) * T::PI.sqrt());
+        result.set(
+            0,
+            Complex::new(ch.band0() / (T::from_f64(2.0) * T::PI).sqrt(), T::ZERO),
+        );
+        for band in 1..ch.band_count() {
+            let (x, y) = ch.band(band);
+            let k = band as isize;
+            result.set(k, Complex::new(x, -y).scale(inv_2_sqrt_pi));
+            result.set(-k, Complex::new(x, y).scale(inv_2_sqrt_pi));
+        }
+
+        result
+    }
+
+    // Only the real part of the function is kept, which is all of it when c[-k] = conj(c[k])
+    pub fn to_ch(&self) -> CircularHarmonics<T> {
+        let mut result = CircularHarmonics::new(self.band_count());
+        let sqrt_pi = T::PI.sqrt();
+        result.set_band0(self.get(0).re * (T::from_f64(2.0) * T::PI).sqrt());
+        for band in 1..self.band_count() {
+            let k = band as isize;
+            let (pos, neg) = (self.get(k), self.get(-k));
+            let x = (pos.re + neg.re) * sqrt_pi;
+            let y = (neg.im - pos.im) * sqrt_pi;
+            result.set_band(band, x, y);
+        }
+
+        result
+    }
+
+    pub fn band_count(&self) -> usize {
+        self.coeffs.len() / 2 + 1
+    }
+
+    // Coefficient of e^(ikθ)
+    pub fn get(&self, k: isize) -> Complex<T> {
+        self.try_get(k).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_get(&self, k: isize) -> Result<Complex<T>, ChError> {
+        Ok(self.coeffs[self.try_index(k)?])
+    }
+
+    pub fn set(&mut self, k: isize, c: Complex<T>) {
+        self.try_set(k, c).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_set(&mut self, k: isize, c: Complex<T>) -> Result<(), ChError> {
+        let index = self.try_index(k)?;
+        self.coeffs[index] = c;
+        Ok(())
+    }
+
+    fn index(&self, k: isize) -> usize {
+        self.try_index(k).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    // c[k] and c[-k] both belong to band |k|
+    fn try_index(&self, k: isize) -> Result<usize, ChError> {
+        let max_k = self.band_count() as isize - 1;
+        if k.abs() > max_k {
+            return Err(ChError::BandOutOfRange {
+                band: k.unsigned_abs(),
+                band_count: self.band_count(),
+            });
+        }
+        Ok((k + max_k) as usize)
+    }
+
+    pub fn evaluate(&self, angle: T) -> Complex<T> {
+        let max_k = self.band_count() as isize - 1;
+        self.coeffs
+            .iter()
+            .enumerate()
+            .map(|(i, &c)| {
+                c * Complex::from_angle(angle * T::from_f64((i as isize - max_k) as f64))
+            })
+            .fold(Complex::default(), |a, b| a + b)
+    }
+
+    // Pointwise product of both functions. The coefficients of a product are the (Cauchy)
+    // convolution of the coefficients, so the result has band count n + m - 1 and is exact.
+    pub fn multiply(&self, other: &ComplexHarmonics<T>) -> ComplexHarmonics<T> {
+        let mut result = Self::new(self.band_count() + other.band_count() - 1);
+        let (max_a, max_b) = (
+            self.band_count() as isize - 1,
+            other.band_count() as isize - 1,
+        );
+        for (i, &a) in self.coeffs.iter().enumerate() {
+            for (j, &b) in other.coeffs.iter().enumerate() {
+                let k = (i as isize - max_a) + (j as isize - max_b);
+                let index = result.index(k);
+                result.coeffs[index] = result.coeffs[index] + a * b;
+            }
+        }
+
+        result
+    }
+
+    // Circular convolution, (f ∗ g)(θ) = ∫ f(φ)·g(θ - φ) dφ, which is 2π·c[k]·d[k] for each
+    // frequency. Frequencies missing from either side are 0, so the result has the smaller
+    // band count.
+    pub fn convolve(&self, kernel: &ComplexHarmonics<T>) -> ComplexHarmonics<T> {
+        let band_count = self.band_count().min(kernel.band_count());
+        let two_pi = T::from_f64(2.0) * T::PI;
+        let mut result = Self::new(band_count);
+        let max_k = band_count as isize - 1;
+        for k in -max_k..=max_k {
+            result.set(k, (self.get(k) * kernel.get(k)).scale(two_pi));
+        }
+
+        result
+    }
+}
+
+impl<T: Scalar> From<&CircularHarmonics<T>> for ComplexHarmonics<T> {
+    fn from(ch: &CircularHarmonics<T>) -> ComplexHarmonics<T> {
+        ComplexHarmonics::from_ch(ch)
+    }
+}
+
+impl<T: Scalar> From<&ComplexHarmonics<T>> for CircularHarmonics<T> {
+    fn from(ch: &ComplexHarmonics<T>) -> CircularHarmonics<T> {
+        ch.to_ch()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_ch(band_count: usize, seed: f64) -> CircularHarmonics<f64> {
+        CircularHarmonics::from_coeffs(
+            (0..band_count * 2 - 1)
+                .map(|i| (i as f64 * 1.3 + seed).sin())
+                .collect(),
+        )
+    }
+
+    fn assert_ch_close(a: &CircularHarmonics<f64>, b: &CircularHarmonics<f64>) {
+        assert_eq!(a.band_count(), b.band_count());
+        let d = a.distance(b);
+        assert!(d < 1e-12, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn round_trip() {
+        let ch = test_ch(5, 0.0);
+        let complex = ComplexHarmonics::from_ch(&ch);
+        assert_ch_close(&complex.to_ch(), &ch);
+        assert_eq!(
+            ComplexHarmonics::from_coeffs(complex.coeffs().to_vec()),
+            complex
+        );
+
+        // c[-k] = conj(c[k]), and evaluating gives the real function
+        assert_eq!(complex.coeffs().len(), 9);
+        for k in 1..5 {
+            assert_eq!(complex.get(-k), complex.get(k).conj());
+        }
+        for i in 0..16 {
+            let angle = i as f64 * 0.4;
+            let value = complex.evaluate(angle);
+            assert!((value.re - ch.evaluate(angle)).abs() < 1e-12 && value.im.abs() < 1e-12);
+        }
+
+        assert_eq!(
+            ComplexHarmonics::<f64>::try_from_coeffs(vec![]),
+            Err(ChError::NoBands)
+        );
+        assert_eq!(
+            ComplexHarmonics::<f64>::try_from_coeffs(vec![Complex::default(); 4]),
+            Err(ChError::EvenCoeffCount(4))
+        );
+    }
+
+    #[test]
+    fn matches_ch_multiply_and_convolve() {
+        let (a, b) = (test_ch(4, 0.0), test_ch(6, 1.0));
+        let (ca, cb) = (ComplexHarmonics::from_ch(&a), ComplexHarmonics::from_ch(&b));
+        assert_ch_close(&ca.multiply(&cb).to_ch(), &a.product(&b, 9));
+
+        let b = test_ch(4, 1.0);
+        let cb = ComplexHarmonics::from_ch(&b);
+        assert_ch_close(&ca.convolve(&cb).to_ch(), &a.convolve(&b));
+    }
+}
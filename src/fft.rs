@@ -0,0 +1,201 @@
+// Small FFT implementation, so CH can be computed from uniformly sampled data in O(N log N).
+// Power-of-two sizes use an iterative radix-2 FFT, and any other size goes through
+// Bluestein's algorithm, which turns the DFT into a power-of-two convolution.
+
+use crate::complex::Complex;
+use crate::scalar::Scalar;
+
+// In-place radix-2 FFT. data.len() must be a power of two.
+// sign is -1 for the forward transform and 1 for the (unnormalized) inverse one.
+fn fft_radix2<T: Scalar>(data: &mut [Complex<T>], sign: T) {
+    let n = data.len();
+    if n <= 1 {
+        return;
+    }
+
+    // Bit reversal permutation
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            data.swap(i, j);
+        }
+    }
+
+    let mut len = 2;
+    while len <= n {
+        // Twiddles are computed directly rather than by repeated multiplication, to avoid
+        // accumulating error on large sizes
+        let twiddles: Vec<Complex<T>> = (0..len / 2)
+            .map(|k| {
+                Complex::from_angle(
+                    sign * T::from_f64(2.0) * T::PI * T::from_usize(k) / T::from_usize(len),
+                )
+            })
+            .collect();
+        for start in (0..n).step_by(len) {
+            for (k, w) in twiddles.iter().enumerate() {
+                let a = data[start + k];
+                let b = data[start + k + len / 2] * *w;
+                data[start + k] = a + b;
+                data[start + k + len / 2] = a - b;
+            }
+        }
+        len <<= 1;
+    }
+}
+
+// Bluestein's algorithm, for sizes that aren't a power of two
+fn fft_bluestein<T: Scalar>(data: &mut [Complex<T>], sign: T) {
+    let n = data.len();
+    let m = (2 * n - 1).next_power_of_two();
+
+    // Chirp: w[k] = e^(sign·iπk²/n). k² is wrapped to keep the angle small and precise
+    let chirp: Vec<Complex<T>> = (0..n)
+        .map(|k| {
+            let k2 = (k * k) % (2 * n);
+            Complex::from_angle(sign * T::PI * T::from_usize(k2) / T::from_usize(n))
+        })
+        .collect();
+
+    let mut a = vec![Complex::default(); m];
+    for k in 0..n {
+        a[k] = data[k] * chirp[k];
+    }
+
+    let mut b = vec![Complex::default(); m];
+    b[0] = chirp[0].conj();
+    for k in 1..n {
+        b[k] = chirp[k].conj();
+        b[m - k] = chirp[k].conj();
+    }
+
+    fft_radix2(&mut a, -T::ONE);
+    fft_radix2(&mut b, -T::ONE);
+    for (x, y) in a.iter_mut().zip(b.iter()) {
+        *x = *x * *y;
+    }
+    fft_radix2(&mut a, T::ONE);
+
+    let inv_m = T::ONE / T::from_usize(m);
+    for k in 0..n {
+        data[k] = a[k] * chirp[k].scale(inv_m);
+    }
+}
+
+fn transform<T: Scalar>(data: &mut [Complex<T>], sign: T) {
+    if data.len().is_power_of_two() {
+        fft_radix2(data, sign);
+    } else if data.len() > 1 {
+        fft_bluestein(data, sign);
+    }
+}
+
+// Forward DFT: X[k] = Σ x[j]·e^(-2πijk/n)
+pub fn fft<T: Scalar>(data: &mut [Complex<T>]) {
+    transform(data, -T::ONE);
+}
+
+// Inverse DFT, including the 1/n normalization: x[j] = 1/n · Σ X[k]·e^(2πijk/n)
+pub fn ifft<T: Scalar>(data: &mut [Complex<T>]) {
+    transform(data, T::ONE);
+    let inv_n = T::ONE / T::from_usize(data.len());
+    for x in data.iter_mut() {
+        *x = x.scale(inv_n);
+    }
+}
+
+// Forward DFT of real data. Returns the n / 2 + 1 non-redundant bins; the rest are their
+// complex conjugates. Even sizes are packed into a complex FFT of half the size.
+pub fn rfft<T: Scalar>(samples: &[T]) -> Vec<Complex<T>> {
+    let n = samples.len();
+    if n % 2 == 1 || n < 2 {
+        let mut data: Vec<Complex<T>> = samples.iter().map(|&x| Complex::new(x, T::ZERO)).collect();
+        fft(&mut data);
+        data.truncate(n / 2 + 1);
+        return data;
+    }
+
+    let half = n / 2;
+    let mut z: Vec<Complex<T>> = (0..half)
+        .map(|j| Complex::new(samples[2 * j], samples[2 * j + 1]))
+        .collect();
+    fft(&mut z);
+
+    // Split the spectrum of the packed signal back into the even and odd sample spectra
+    (0..=half)
+        .map(|k| {
+            let zk = z[k % half];
+            let zk_mirror = z[(half - k) % half].conj();
+            let half_one = T::from_f64(0.5);
+            let even = (zk + zk_mirror).scale(half_one);
+            let odd = (zk - zk_mirror) * Complex::new(T::ZERO, -half_one);
+            let angle = -T::from_f64(2.0) * T::PI * T::from_usize(k) / T::from_usize(n);
+            even + odd * Complex::from_angle(angle)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn naive_dft(data: &[Complex<f64>]) -> Vec<Complex<f64>> {
+        let n = data.len();
+        (0..n)
+            .map(|k| {
+                data.iter()
+                    .enumerate()
+                    .map(|(j, &x)| {
+                        let angle = -2.0 * std::f64::consts::PI * ((j * k) % n) as f64 / n as f64;
+                        x * Complex::from_angle(angle)
+                    })
+                    .fold(Complex::default(), |a, b| a + b)
+            })
+            .collect()
+    }
+
+    fn test_signal(n: usize) -> Vec<f64> {
+        (0..n)
+            .map(|j| (j as f64 * 0.7).sin() + 0.1 * j as f64)
+            .collect()
+    }
+
+    fn assert_close(a: &[Complex<f64>], b: &[Complex<f64>]) {
+        assert_eq!(a.len(), b.len());
+        for (x, y) in a.iter().zip(b.iter()) {
+            assert!((x.re - y.re).abs() < 1e-9 && (x.im - y.im).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn fft_matches_naive_dft() {
+        for n in 1..=33 {
+            let data: Vec<Complex<f64>> = test_signal(n)
+                .iter()
+                .zip(test_signal(n + 1).iter().skip(1))
+                .map(|(&re, &im)| Complex::new(re, im))
+                .collect();
+            let mut transformed = data.clone();
+            fft(&mut transformed);
+            assert_close(&transformed, &naive_dft(&data));
+
+            ifft(&mut transformed);
+            assert_close(&transformed, &data);
+        }
+    }
+
+    #[test]
+    fn rfft_matches_naive_dft() {
+        for n in 1..=33 {
+            let samples = test_signal(n);
+            let data: Vec<Complex<f64>> = samples.iter().map(|&x| Complex::new(x, 0.0)).collect();
+            assert_close(&rfft(&samples), &naive_dft(&data)[..n / 2 + 1]);
+        }
+    }
+}
@@ -0,0 +1,11 @@
+// The CH math, as a library so main.rs and the benches share it
+pub mod circ_harmonics;
+pub mod circ_harmonics_batch;
+pub mod circ_harmonics_n;
+pub mod complex;
+pub mod complex_harmonics;
+mod fft;
+pub mod linear_operator;
+mod lobes;
+mod polynomial;
+pub mod scalar;
@@ -0,0 +1,146 @@
+// Linear transforms of CH coefficients as plain matrices. Rotations, reflections, windows and
+// convolutions are all linear, so any chain of them can be composed into a single matrix once,
+// and then applied to as many CH as needed.
+
+use crate::circ_harmonics::{check_band_count, window_weight, CircularHarmonics, WindowKind};
+use crate::scalar::Scalar;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct LinearOperator<T: Scalar = f32> {
+    band_count: usize,
+    // coeff_count x coeff_count, row-major. Row i computes output coefficient i.
+    matrix: Vec<T>,
+}
+
+fn to_coeffs<T: Scalar>(ch: &CircularHarmonics<T>) -> Vec<T> {
+    let mut coeffs = vec![ch.band0()];
+    for band in 1..ch.band_count() {
+        let (x, y) = ch.band(band);
+        coeffs.push(x);
+        coeffs.push(y);
+    }
+
+    coeffs
+}
+
+impl<T: Scalar> LinearOperator<T> {
+    // Builds a matrix that only mixes the two coefficients of each band. band0 scales band 0, and
+    // block(k) is the 2x2 matrix applied to band k's (x, y).
+    fn from_bands(
+        band_count: usize,
+        band0: T,
+        block: impl Fn(usize) -> [[T; 2]; 2],
+    ) -> LinearOperator<T> {
+        check_band_count(band_count).unwrap_or_else(|e| panic!("{}", e));
+        let mut result = LinearOperator {
+            band_count,
+            matrix: vec![T::ZERO; (band_count * 2 - 1) * (band_count * 2 - 1)],
+        };
+        let n = result.coeff_count();
+        result.matrix[0] = band0;
+        for band in 1..band_count {
+            let m = block(band);
+            let (x, y) = (band * 2 - 1, band * 2);
+            result.matrix[x * n + x] = m[0][0];
+            result.matrix[x * n + y] = m[0][1];
+            result.matrix[y * n + x] = m[1][0];
+            result.matrix[y * n + y] = m[1][1];
+        }
+
+        result
+    }
+
+    pub fn identity(band_count: usize) -> LinearOperator<T> {
+        Self::from_bands(band_count, T::ONE, |_| {
+            [[T::ONE, T::ZERO], [T::ZERO, T::ONE]]
+        })
+    }
+
+    // Same as CircularHarmonics::rotate
+    pub fn rotation(band_count: usize, angle: T) -> LinearOperator<T> {
+        Self::from_bands(band_count, T::ONE, |band| {
+            let (s, c) = (angle * T::from_usize(band)).sin_cos();
+            [[c, -s], [s, c]]
+        })
+    }
+
+    // Same as CircularHarmonics::negate_angle
+    pub fn negate_angle(band_count: usize) -> LinearOperator<T> {
+        Self::from_bands(band_count, T::ONE, |_| {
+            [[T::ONE, T::ZERO], [T::ZERO, -T::ONE]]
+        })
+    }
+
+    // Same as CircularHarmonics::mirror
+    pub fn reflection(band_count: usize, axis_angle: T) -> LinearOperator<T> {
+        Self::from_bands(band_count, T::ONE, |band| {
+            let (s, c) = (axis_angle * T::from_f64(2.0) * T::from_usize(band)).sin_cos();
+            [[c, s], [s, -c]]
+        })
+    }
+
+    // Same as CircularHarmonics::apply_window
+    pub fn window(band_count: usize, window: WindowKind<T>) -> LinearOperator<T> {
+        Self::from_bands(band_count, T::ONE, |band| {
+            let w = window_weight(window, band, band_count);
+            [[w, T::ZERO], [T::ZERO, w]]
+        })
+    }
+
+    // Same as CircularHarmonics::convolve with this kernel. Bands the kernel doesn't have are
+    // zeroed.
+    pub fn convolution(band_count: usize, kernel: &CircularHarmonics<T>) -> LinearOperator<T> {
+        let band0 = (T::from_f64(2.0) * T::PI).sqrt() * kernel.band0();
+        Self::from_bands(band_count, band0, |band| {
+            if band >= kernel.band_count() {
+                return [[T::ZERO; 2]; 2];
+            }
+            let (bx, by) = kernel.band(band);
+            let sqrt_pi = T::PI.sqrt();
+            [[sqrt_pi * bx, -sqrt_pi * by], [sqrt_pi * by, sqrt_pi * bx]]
+        })
+    }
+
+    pub fn band_count(&self) -> usize {
+        self.band_count
+    }
+
+    pub fn coeff_count(&self) -> usize {
+        self.band_count * 2 - 1
+    }
+
+    // Returns the operator that applies `first`, and then this one
+    pub fn compose(&self, first: &LinearOperator<T>) -> LinearOperator<T> {
+        assert_eq!(self.band_count, first.band_count);
+        let n = self.coeff_count();
+        let mut matrix = vec![T::ZERO; n * n];
+        for i in 0..n {
+            for k in 0..n {
+                let a = self.matrix[i * n + k];
+                if a == T::ZERO {
+                    continue;
+                }
+                for j in 0..n {
+                    matrix[i * n + j] += a * first.matrix[k * n + j];
+                }
+            }
+        }
+
+        LinearOperator {
+            band_count: self.band_count,
+            matrix,
+        }
+    }
+
+    // CH with a different band count get truncated or zero-extended first
+    pub fn apply(&self, ch: &CircularHarmonics<T>) -> CircularHarmonics<T> {
+        let n = self.coeff_count();
+        let coeffs = to_coeffs(&ch.with_band_count(self.band_count));
+        CircularHarmonics::from_coeffs(
+            self.matrix
+                .chunks(n)
+                .map(|row| row.iter().zip(coeffs.iter()).map(|(&m, &c)| m * c).sum())
+                .collect(),
+        )
+    }
+}
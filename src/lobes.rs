@@ -0,0 +1,138 @@
+// Closed-form projections of common lobe shapes, centered at angle 0. All the lobes are even
+// functions, so they only have cosine terms. Every function here returns the integral
+// ∫[-π, π] f(θ)·cos(kθ) dθ for band k, and the CH constructors scale and rotate them.
+// Everything is done in f64, since the special functions need the extra precision.
+
+use std::f64::consts::PI;
+
+// max(cos(θ), 0)
+pub(crate) fn clamped_cosine(k: usize) -> f64 {
+    // ½∫[-π/2, π/2] cos((k - 1)θ) + cos((k + 1)θ)
+    let half_period = |m: f64| (m * PI * 0.5).sin() / m;
+    match k {
+        0 => 2.0,
+        1 => PI * 0.5,
+        k => half_period(k as f64 - 1.0) + half_period(k as f64 + 1.0),
+    }
+}
+
+// e^(κ·(cos(θ) - 1)), a von Mises lobe scaled so its peak is 1. The integral is 2π·I_k(κ)·e^(-κ).
+pub(crate) fn von_mises(kappa: f64, band_count: usize) -> Vec<f64> {
+    scaled_bessel_i(kappa, band_count)
+        .into_iter()
+        .map(|i| 2.0 * PI * i)
+        .collect()
+}
+
+// 1 - |θ| / w for |θ| < w, 0 elsewhere. w must be in (0, π].
+pub(crate) fn tent(half_width: f64, k: usize) -> f64 {
+    if k == 0 {
+        return half_width;
+    }
+    let k = k as f64;
+    2.0 * (1.0 - (k * half_width).cos()) / (k * k * half_width)
+}
+
+// ½·(1 + cos(πθ / w)) for |θ| < w, 0 elsewhere. w must be in (0, π].
+pub(crate) fn raised_cosine(half_width: f64, k: usize) -> f64 {
+    if k == 0 {
+        return half_width;
+    }
+    let k = k as f64;
+    let beta = PI / half_width;
+    if (k - beta).abs() < 1e-9 {
+        // Limit of the expression below as k -> β
+        return half_width * 0.5;
+    }
+    (k * half_width).sin() * beta * beta / (k * (beta * beta - k * k))
+}
+
+// max(cos(θ), 0)ⁿ, the usual Phong lobe. The integral is
+// π·Γ(n + 1) / (2ⁿ·Γ((n + k) / 2 + 1)·Γ((n - k) / 2 + 1)), done in log space so large exponents
+// don't overflow.
+pub(crate) fn phong(exponent: f64, k: usize) -> f64 {
+    let k = k as f64;
+    let a = (exponent + k) * 0.5 + 1.0;
+    let b = (exponent - k) * 0.5 + 1.0;
+    let log = ln_gamma(exponent + 1.0) - exponent * 2f64.ln() - ln_gamma(a);
+    if b > 0.0 {
+        return PI * (log - ln_gamma(b)).exp();
+    }
+
+    // 1/Γ(b) = sin(πb)·Γ(1 - b) / π, which is 0 at the poles of Γ
+    if (b - b.round()).abs() < 1e-12 {
+        return 0.0;
+    }
+    (PI * b).sin() * (log + ln_gamma(1.0 - b)).exp()
+}
+
+// ln(Γ(x)) for x > 0, using the Lanczos approximation (g = 7)
+fn ln_gamma(x: f64) -> f64 {
+    const COEFFS: [f64; 9] = [
+        0.999_999_999_999_809_9,
+        676.520_368_121_885_1,
+        -1_259.139_216_722_402_8,
+        771.323_428_777_653_1,
+        -176.615_029_162_140_6,
+        12.507_343_278_686_905,
+        -0.138_571_095_265_720_12,
+        9.984_369_578_019_572e-6,
+        1.505_632_735_149_311_6e-7,
+    ];
+
+    if x < 0.5 {
+        // Reflection formula, since the approximation is only good for x >= 0.5
+        return (PI / (PI * x).sin()).ln() - ln_gamma(1.0 - x);
+    }
+
+    let x = x - 1.0;
+    let t = x + 7.5;
+    let series = COEFFS[1..]
+        .iter()
+        .enumerate()
+        .fold(COEFFS[0], |acc, (i, &c)| acc + c / (x + i as f64 + 1.0));
+    0.5 * (2.0 * PI).ln() + (x + 0.5) * t.ln() - t + series.ln()
+}
+
+// I_k(κ)·e^(-κ) for k in 0..count, the exponentially scaled modified Bessel functions of the
+// first kind. Uses Miller's backward recurrence, I_(k-1) = I_(k+1) + (2k / κ)·I_k, started well
+// above the highest order needed and normalized with e^(-κ)·(I_0 + 2·Σ I_k) = 1.
+fn scaled_bessel_i(kappa: f64, count: usize) -> Vec<f64> {
+    let mut result = vec![0.0; count];
+    if kappa <= 0.0 {
+        // I_0(0) = 1, and every other order is 0
+        if let Some(first) = result.first_mut() {
+            *first = 1.0;
+        }
+        return result;
+    }
+
+    let start = count + 30 + (40.0 * kappa).sqrt() as usize;
+    let (mut next, mut current) = (0.0, 1e-300);
+    let mut sum = 0.0;
+    for k in (1..=start).rev() {
+        let previous = next + 2.0 * k as f64 / kappa * current;
+        next = current;
+        current = previous;
+        if k - 1 < count {
+            result[k - 1] = current;
+        }
+        sum += if k > 1 { 2.0 * current } else { current };
+
+        // Keep the values in range, the ratios are all that matter
+        if current > 1e250 {
+            next *= 1e-250;
+            current *= 1e-250;
+            sum *= 1e-250;
+            for r in result.iter_mut() {
+                *r *= 1e-250;
+            }
+        }
+    }
+
+    for r in result.iter_mut() {
+        *r /= sum;
+    }
+
+    result
+}
@@ -1,22 +1,31 @@
 #![allow(mixed_script_confusables)]
 
 use nannou::prelude::*;
-mod circ_harmonics;
 mod spherical_harmonics;
 
-use circ_harmonics::*;
+use circular_harmonics::circ_harmonics::*;
 use spherical_harmonics::SphericalHarmonics;
 
 use std::f32::consts::PI;
 const TWOPI: f32 = PI * 2.0;
 // -------------------------------------------------------
 
+// Operation shown in the CH composition scene
+#[derive(Clone, Copy)]
+enum Composition {
+    Add,
+    Subtract,
+    Multiply,
+    Convolve,
+}
+
 struct Model {
     _window: window::Id,
     frame_count: usize,
     recording: bool,
     playing: bool,
     scene: usize,
+    composition: Composition,
 }
 
 fn model(app: &App) -> Model {
@@ -36,6 +45,7 @@ fn model(app: &App) -> Model {
         recording: false,
         playing: false,
         scene: 1,
+        composition: Composition::Add,
     }
 }
 
@@ -58,6 +68,19 @@ fn update(app: &App, model: &mut Model, _update: Update) {
         }
     }
 
+    let composition_keys = [
+        (Key::A, Composition::Add),
+        (Key::S, Composition::Subtract),
+        (Key::M, Composition::Multiply),
+        (Key::C, Composition::Convolve),
+    ];
+
+    for k in composition_keys {
+        if app.keys.down.get(&k.0).is_some() {
+            model.composition = k.1;
+        }
+    }
+
     // Rewind
     if app.keys.down.get(&Key::W).is_some() {
         model.recording = false;
@@ -239,7 +262,8 @@ fn view(app: &App, model: &Model, frame: Frame) {
             };
 
             for bands in 2..=5 {
-                let ch_impulse = CircularHarmonics::from_impulse(bands, PI, 0.7);
+                let ch_impulse =
+                    CircularHarmonics::from_impulse(bands, PI, 1.0, Normalization::UnitPeak);
                 let offsetx = bands as f32 * 700.0 - 2300.0;
                 for i in 0..=grid_size {
                     draw_grid_row(i, &ctx, offsetx, 0.0);
@@ -267,7 +291,9 @@ fn view(app: &App, model: &Model, frame: Frame) {
             let bands_opts = [2, 4, 6, 8, 12, 16, 32, 64];
             let angle = PI * 0.6 + 0.5 * PI * (model.frame_count as f32 * 0.02).sin();
             for (i, bands) in bands_opts.iter().enumerate() {
-                let ch_pulse = CircularHarmonics::from_pulse(*bands, angle, 1.0).rotate(PI * 0.5);
+                let ch_pulse =
+                    CircularHarmonics::from_pulse(*bands, angle, 1.0, Normalization::Raw)
+                        .rotate(PI * 0.5);
                 let offsetx = (i % 4) as f32 * 700.0 - 1050.0;
                 let offsety = 500.0 - (i / 4) as f32 * 800.0;
 
@@ -429,7 +455,8 @@ fn view(app: &App, model: &Model, frame: Frame) {
             };
 
             let angle = PI * 0.1 + 0.9 * PI * wrap;
-            let ch_pulse = CircularHarmonics::from_pulse(20, angle, 1.0).rotate(PI * 0.5);
+            let ch_pulse =
+                CircularHarmonics::from_pulse(20, angle, 1.0, Normalization::Raw).rotate(PI * 0.5);
             let rotation = wrap * TWOPI;
             let ch_pulse = ch_pulse.rotate(rotation);
             let offsetx = 0.0;
@@ -459,13 +486,18 @@ fn view(app: &App, model: &Model, frame: Frame) {
             };
 
             let angle = model.frame_count as f32 * TWOPI / 300.0;
-            //let ch_pulse1 = CircularHarmonics::from_pulse(20, 2.0*PI, 1.0).rotate(PI * 0.5);
-            let ch_pulse1 = CircularHarmonics::from_pulse(20, PI, 1.0).rotate(PI * 0.5);
-            let ch_pulse2 = CircularHarmonics::from_pulse(20, PI * 0.25, 1.0).rotate(angle);
-
-            let ch_pulse = &ch_pulse1 + &ch_pulse2;
-            //let ch_pulse = &ch_pulse1 - &ch_pulse2;
-            // let ch_pulse = &ch_pulse2.convolve(&ch_pulse1);
+            //let ch_pulse1 = CircularHarmonics::from_pulse(20, 2.0*PI, 1.0, Normalization::Raw).rotate(PI * 0.5);
+            let ch_pulse1 =
+                CircularHarmonics::from_pulse(20, PI, 1.0, Normalization::Raw).rotate(PI * 0.5);
+            let ch_pulse2 =
+                CircularHarmonics::from_pulse(20, PI * 0.25, 1.0, Normalization::Raw).rotate(angle);
+
+            let (ch_pulse, operator) = match model.composition {
+                Composition::Add => (&ch_pulse1 + &ch_pulse2, "+"),
+                Composition::Subtract => (&ch_pulse1 - &ch_pulse2, "-"),
+                Composition::Multiply => (&ch_pulse1 * &ch_pulse2, "×"),
+                Composition::Convolve => (ch_pulse2.convolve(&ch_pulse1), "∗"),
+            };
 
             let offsetx = -250.0;
 
@@ -482,8 +514,7 @@ fn view(app: &App, model: &Model, frame: Frame) {
             draw_ch_and_grid(&ctx, &ch_pulse2, offsetx, 0.0);
             draw_ch_and_grid(&ctx, &ch_pulse, offsetx + 1000.0, 0.0);
 
-            //draw.text("+")
-            draw.text("-")
+            draw.text(operator)
                 .color(BLACK)
                 //.font(model.font.clone())
                 .font_size(130)
@@ -496,6 +527,48 @@ fn view(app: &App, model: &Model, frame: Frame) {
                 .x_y(250.0, -80.0);
         }
 
+        // Windowing
+        8 => {
+            let ctx = Context {
+                zoom: 200.0,
+                wrap: 0.0,
+                ch_stroke_width: 5.0,
+                grid_stroke_width: 3.0,
+                angle_multiplier: 1.0,
+            };
+
+            let windows = [
+                ("None", None),
+                ("Lanczos", Some(WindowKind::Lanczos)),
+                ("Hann", Some(WindowKind::Hann)),
+                ("Hamming", Some(WindowKind::Hamming)),
+                ("Gaussian", Some(WindowKind::Gaussian(0.4))),
+                ("Fejér", Some(WindowKind::Fejer)),
+            ];
+            let angle = PI * 0.6 + 0.5 * PI * (model.frame_count as f32 * 0.02).sin();
+            for (i, (name, window)) in windows.iter().enumerate() {
+                let mut ch_pulse =
+                    CircularHarmonics::from_pulse(12, angle, 1.0, Normalization::Raw)
+                        .rotate(PI * 0.5);
+                if let Some(window) = window {
+                    ch_pulse.apply_window(*window);
+                }
+                let offsetx = (i % 3) as f32 * 800.0 - 800.0;
+                let offsety = 500.0 - (i / 3) as f32 * 800.0;
+
+                for i in 0..=grid_size {
+                    draw_grid_row(i, &ctx, offsetx, offsety);
+                    draw_grid_col(i, &ctx, offsetx, offsety);
+                }
+                draw_ch(&ch_pulse, true, offsetx, offsety, &ctx);
+                draw.text(name)
+                    .color(BLACK)
+                    .font_size(55)
+                    .no_line_wrap()
+                    .x_y(offsetx, offsety - 450.0);
+            }
+        }
+
         // Fourier unwrapping
         9 => {
             let ctx = Context {
@@ -506,8 +579,10 @@ fn view(app: &App, model: &Model, frame: Frame) {
                 angle_multiplier: 1.0,
             };
             let bands = 19;
-            let ch_impulse = CircularHarmonics::from_impulse(bands, PI, 0.2);
-            let ch_pulse = CircularHarmonics::from_pulse(20, PI, 1.0).rotate(PI * 0.5);
+            let ch_impulse =
+                CircularHarmonics::from_impulse(bands, PI, 1.0, Normalization::UnitPeak);
+            let ch_pulse =
+                CircularHarmonics::from_pulse(20, PI, 1.0, Normalization::Raw).rotate(PI * 0.5);
 
             for i in 0..=grid_size {
                 draw_grid_row(i, &ctx, 0.0, 300.0);
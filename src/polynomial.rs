@@ -0,0 +1,216 @@
+// Roots of polynomials with complex coefficients, found as the eigenvalues of their companion
+// matrix. Everything is done in f64, since root finding is a lot more sensitive to rounding
+// than evaluating the polynomial.
+
+use crate::complex::Complex;
+
+type C64 = Complex<f64>;
+
+// Balances a matrix (n x n, row-major) with diagonal similarity transforms, so rows and columns
+// have comparable norms. This doesn't change the eigenvalues, but makes them more accurate.
+fn balance(a: &mut [C64], n: usize) {
+    let radix = 2.0f64;
+    let mut done = false;
+    while !done {
+        done = true;
+        for i in 0..n {
+            let mut r = 0.0;
+            let mut c = 0.0;
+            for j in 0..n {
+                if j != i {
+                    c += a[j * n + i].abs();
+                    r += a[i * n + j].abs();
+                }
+            }
+            if c == 0.0 || r == 0.0 {
+                continue;
+            }
+
+            let s = c + r;
+            let mut f = 1.0;
+            let mut g = r / radix;
+            while c < g {
+                f *= radix;
+                c *= radix * radix;
+            }
+            g = r * radix;
+            while c > g {
+                f /= radix;
+                c /= radix * radix;
+            }
+
+            if (c + r) / f < 0.95 * s {
+                done = false;
+                for j in 0..n {
+                    a[i * n + j] = a[i * n + j].scale(1.0 / f);
+                    a[j * n + i] = a[j * n + i].scale(f);
+                }
+            }
+        }
+    }
+}
+
+// Eigenvalues of an upper Hessenberg matrix (n x n, row-major), using shifted QR iterations
+// with Givens rotations and deflation.
+fn hessenberg_eigenvalues(h: &mut [C64], n: usize) -> Vec<C64> {
+    let mut eigenvalues = vec![C64::default(); n];
+    if n == 0 {
+        return eigenvalues;
+    }
+
+    let mut hi = n - 1;
+    let mut iterations = 0;
+    while hi > 0 {
+        // Look for a negligible subdiagonal element to split the matrix at
+        let mut lo = hi;
+        while lo > 0 {
+            let off = h[lo * n + lo - 1].abs();
+            let diag = h[(lo - 1) * n + lo - 1].abs() + h[lo * n + lo].abs();
+            if off <= f64::EPSILON * diag.max(f64::MIN_POSITIVE) {
+                h[lo * n + lo - 1] = C64::default();
+                break;
+            }
+            lo -= 1;
+        }
+
+        if lo == hi {
+            eigenvalues[hi] = h[hi * n + hi];
+            hi -= 1;
+            iterations = 0;
+            continue;
+        }
+
+        iterations += 1;
+        if iterations > 1000 {
+            // Give up on convergence, and take the diagonal as the best estimate we have
+            for i in 0..=hi {
+                eigenvalues[i] = h[i * n + i];
+            }
+            return eigenvalues;
+        }
+
+        // Wilkinson shift: the eigenvalue of the trailing 2x2 block closest to its last element.
+        // Every now and then use an exceptional shift, to break out of cycles.
+        let a = h[(hi - 1) * n + hi - 1];
+        let b = h[(hi - 1) * n + hi];
+        let c = h[hi * n + hi - 1];
+        let d = h[hi * n + hi];
+        let shift = if iterations % 11 == 10 {
+            d + Complex::new(h[hi * n + hi - 1].abs() * 0.75, 0.0)
+        } else {
+            let half_diff = (a - d).scale(0.5);
+            let disc = (half_diff * half_diff + b * c).sqrt();
+            let mid = (a + d).scale(0.5);
+            let (m0, m1) = (mid + disc, mid - disc);
+            if (m0 - d).abs() < (m1 - d).abs() {
+                m0
+            } else {
+                m1
+            }
+        };
+
+        for i in lo..=hi {
+            h[i * n + i] = h[i * n + i] - shift;
+        }
+
+        // H = QR, with Q as a sequence of Givens rotations
+        let mut rotations = Vec::with_capacity(hi - lo);
+        for k in lo..hi {
+            let x = h[k * n + k];
+            let y = h[(k + 1) * n + k];
+            let r = (x.re * x.re + x.im * x.im + y.re * y.re + y.im * y.im).sqrt();
+            let (c, s) = if r == 0.0 {
+                (Complex::new(1.0, 0.0), C64::default())
+            } else {
+                (x.scale(1.0 / r), y.scale(1.0 / r))
+            };
+            for j in k..=hi {
+                let x = h[k * n + j];
+                let y = h[(k + 1) * n + j];
+                h[k * n + j] = c.conj() * x + s.conj() * y;
+                h[(k + 1) * n + j] = c * y - s * x;
+            }
+            rotations.push((c, s));
+        }
+
+        // H = RQ
+        for (k, (c, s)) in (lo..hi).zip(rotations) {
+            for i in lo..=(k + 1).min(hi) {
+                let x = h[i * n + k];
+                let y = h[i * n + k + 1];
+                h[i * n + k] = x * c + y * s;
+                h[i * n + k + 1] = y * c.conj() - x * s.conj();
+            }
+        }
+
+        for i in lo..=hi {
+            h[i * n + i] = h[i * n + i] + shift;
+        }
+    }
+    eigenvalues[0] = h[0];
+
+    eigenvalues
+}
+
+// Roots of Σ coeffs[i]·zⁱ. Leading zero coefficients are ignored.
+pub fn roots(coeffs: &[C64]) -> Vec<C64> {
+    let degree = match coeffs.iter().rposition(|c| c.abs() != 0.0) {
+        Some(degree) => degree,
+        None => return vec![],
+    };
+
+    // Companion matrix of the monic polynomial, which is already in upper Hessenberg form
+    let n = degree;
+    let lead = coeffs[degree];
+    let mut companion = vec![C64::default(); n * n];
+    for j in 0..n {
+        companion[j] = -(coeffs[degree - 1 - j] / lead);
+    }
+    for i in 1..n {
+        companion[i * n + i - 1] = Complex::new(1.0, 0.0);
+    }
+
+    balance(&mut companion, n);
+    hessenberg_eigenvalues(&mut companion, n)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_roots(coeffs: &[C64], expected: &[C64]) {
+        let found = roots(coeffs);
+        assert_eq!(found.len(), expected.len(), "{:?}", found);
+        for e in expected {
+            assert!(
+                found.iter().any(|&r| (r - *e).abs() < 1e-9),
+                "{:?} not in {:?}",
+                e,
+                found
+            );
+        }
+    }
+
+    #[test]
+    fn known_roots() {
+        let real = |x: f64| Complex::new(x, 0.0);
+
+        // (z - 1)(z - 2)(z - 3), with a zero leading coefficient that has to be ignored
+        assert_roots(
+            &[real(-6.0), real(11.0), real(-6.0), real(1.0), real(0.0)],
+            &[real(1.0), real(2.0), real(3.0)],
+        );
+        // z² + 1
+        assert_roots(
+            &[real(1.0), real(0.0), real(1.0)],
+            &[Complex::new(0.0, 1.0), Complex::new(0.0, -1.0)],
+        );
+        // (z - i)(z - 2) = z² - (2 + i)z + 2i
+        assert_roots(
+            &[Complex::new(0.0, 2.0), Complex::new(-2.0, -1.0), real(1.0)],
+            &[Complex::new(0.0, 1.0), real(2.0)],
+        );
+        assert_roots(&[real(3.0)], &[]);
+        assert_roots(&[real(0.0), real(0.0)], &[]);
+    }
+}